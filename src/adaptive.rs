@@ -0,0 +1,76 @@
+use std::{path::Path, time::Duration};
+
+use crate::{cache, config, util};
+
+const KIND: &str = "status-latency";
+
+const DEFAULT_BUDGET: Duration = Duration::from_millis(500);
+
+// how long a repo stays reduced before the full status is measured again
+const RETRY: u64 = 60 * 60;
+
+/// How long the last full `git status` took in a repo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Latency {
+    pub elapsed: Duration,
+    /// Unix seconds.
+    pub measured_at: u64,
+}
+
+/// The latency above which expensive segments are skipped, `None` if adaptation is disabled.
+pub fn budget(config: &config::Config) -> Option<Duration> {
+    match config.get_int("adaptive.budget") {
+        Some(millis) if millis <= 0 => None,
+        Some(millis) => Some(Duration::from_millis(millis as u64)),
+        None => Some(DEFAULT_BUDGET),
+    }
+}
+
+// the git dir identifies the repo regardless of which subdir the prompt is rendered in
+fn key(path: &Path) -> Option<String> {
+    util::find_git_dir(path).map(|git_dir| cache::path_key(&git_dir))
+}
+
+// <millis> <unix secs>
+pub fn last(path: &Path) -> Option<Latency> {
+    let entry = cache::get(KIND, &key(path)?)?;
+    let (millis, measured_at) = entry.split_once(' ')?;
+
+    Some(Latency {
+        elapsed: Duration::from_millis(millis.parse().ok()?),
+        measured_at: measured_at.parse().ok()?,
+    })
+}
+
+/// Remembers the latency of a full status, a failure to do so is not an error.
+pub fn record(path: &Path, elapsed: Duration) {
+    if let Some(key) = key(path) {
+        let entry = format!("{} {}", elapsed.as_millis(), util::unix_now());
+        let _ = cache::set(KIND, &key, &entry);
+    }
+}
+
+/// Whether the last full status exceeded the budget recently enough that untracked files, the
+/// diffstat and `describe` should be skipped.
+///
+/// A change reported by the hooks since the measurement invalidates it, a checkout may well have
+/// made the repo small again.
+pub fn is_reduced(path: &Path, config: &config::Config) -> bool {
+    match (budget(config), last(path)) {
+        (Some(budget), Some(latency)) => {
+            let changed = util::find_git_dir(path)
+                .and_then(|git_dir| cache::stamp(&git_dir))
+                .is_some_and(|stamp| stamp > latency.measured_at);
+
+            !changed
+                && latency.elapsed > budget
+                && util::unix_now().saturating_sub(latency.measured_at) < RETRY
+        }
+        _ => false,
+    }
+}
+
+/// Seconds until a reduced repo is measured in full again.
+pub fn retry_in(latency: &Latency) -> u64 {
+    (latency.measured_at + RETRY).saturating_sub(util::unix_now())
+}
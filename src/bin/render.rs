@@ -0,0 +1,66 @@
+//! `git status --porcelain=v2 --branch --show-stash` on stdin, the prompt on stdout.
+//!
+//! Nothing is spawned and no config is read, which makes this the build for sandboxes without
+//! processes, e.g. `cargo build --no-default-features --target wasm32-wasip1` for web terminals
+//! and documentation playgrounds, which then render the exact same prompt as the shell does.
+
+use std::{
+    env,
+    io::{self, Read},
+    process,
+};
+
+use epb_prompt_git::{config, output, parse_status, repo};
+
+// epb-prompt-render [--no-color] [--html] [--theme=default|icons] [--ascii] [--format=<template>]
+// epb-prompt-render --css
+fn main() {
+    let mut options = repo::RenderOptions {
+        color: true,
+        ..Default::default()
+    };
+    let mut template = None;
+
+    for arg in env::args().skip(1) {
+        if arg == "--no-color" {
+            options.color = false;
+        } else if arg == "--ascii" {
+            options.ascii = true;
+        } else if arg == "--html" {
+            options.markup = repo::Markup::Html;
+        } else if arg == "--css" {
+            print!("{}", output::stylesheet());
+            return;
+        } else if let Some(name) = arg.strip_prefix("--theme=") {
+            options.theme = repo::Theme::from_name(name).unwrap_or_else(|| {
+                eprintln!("epb-prompt-render: unknown theme `{name}`");
+                process::exit(2)
+            });
+        } else if let Some(format) = arg.strip_prefix("--format=") {
+            template = Some(repo::Template::parse(format).unwrap_or_else(|err| {
+                eprintln!("epb-prompt-render: --format: {err}");
+                process::exit(2)
+            }));
+        } else {
+            eprintln!("epb-prompt-render: unknown argument `{arg}`");
+            process::exit(2)
+        }
+    }
+
+    let mut porcelain = String::new();
+    if let Err(err) = io::stdin().read_to_string(&mut porcelain) {
+        eprintln!("epb-prompt-render: {err}");
+        process::exit(1)
+    }
+
+    let snapshot = parse_status(&porcelain, &config::Config::default());
+    for warning in &snapshot.warnings {
+        eprintln!("epb-prompt-render: {warning}");
+    }
+
+    let prompt = snapshot.render_with(&options, template.as_ref());
+    match options.markup {
+        repo::Markup::Html => println!("{}", output::html(&prompt)),
+        _ => println!("{prompt}"),
+    }
+}
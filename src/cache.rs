@@ -0,0 +1,76 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use crate::{dirs, util};
+
+/// The file in the git dir our hooks touch whenever HEAD moves, see `hooks::install`.
+pub const STAMP: &str = "epb-prompt-git.stamp";
+
+// one file per entry, `<cache dir>/<kind>/<key>`
+// keys must be valid file names, commit ids and combinations of them are
+fn entry_path(kind: &str, key: &str) -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join(kind).join(key))
+}
+
+/// A key for entries about arbitrary data, FNV-1a since that is not a valid file name itself.
+pub fn hash_key(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf29ce484222325_u64, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+
+    format!("{hash:016x}")
+}
+
+/// A key for entries about a path.
+pub fn path_key(path: &Path) -> String {
+    hash_key(util::stable_path(path).as_os_str().as_encoded_bytes())
+}
+
+/// Unix seconds at which the hooks last saw the repo change, `None` if they are not installed or
+/// never ran.
+pub fn stamp(git_dir: &Path) -> Option<u64> {
+    let modified = fs::metadata(git_dir.join(STAMP)).ok()?.modified().ok()?;
+    modified
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_secs())
+}
+
+pub fn get(kind: &str, key: &str) -> Option<String> {
+    fs::read_to_string(entry_path(kind, key)?).ok()
+}
+
+pub fn set(kind: &str, key: &str, value: &str) -> io::Result<()> {
+    let Some(path) = entry_path(kind, key) else {
+        return Ok(());
+    };
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    fs::write(path, value)
+}
+
+/// Returns the cached value for `key` or computes, caches and returns it, a failure to cache is
+/// not an error.
+#[cfg(feature = "cli")]
+pub fn get_or_insert_with<E>(
+    kind: &str,
+    key: &str,
+    f: impl FnOnce() -> Result<Option<String>, E>,
+) -> Result<Option<String>, E> {
+    if let Some(value) = get(kind, key) {
+        return Ok(Some(value));
+    }
+
+    let value = f()?;
+    if let Some(value) = &value {
+        let _ = set(kind, key, value);
+    }
+
+    Ok(value)
+}
@@ -0,0 +1,45 @@
+use crate::{config, repo::RepoSnapshot};
+
+/// Whether `branch` is listed in `check.protected`.
+pub fn is_protected(branch: &str, config: &config::Config) -> bool {
+    config
+        .get_list("check.protected")
+        .unwrap_or_default()
+        .iter()
+        .any(|value| matches!(value, config::Value::Str(name) if name == branch))
+}
+
+/// Why committing in the state of `snapshot` would be risky by the `check.*` policy, empty if it
+/// is not.
+pub fn risks(snapshot: &RepoSnapshot, config: &config::Config) -> Vec<String> {
+    let mut risks = vec![];
+
+    if let Some(branch) = &snapshot.branch {
+        if is_protected(branch, config) {
+            risks.push(format!("on protected branch `{branch}`"));
+        }
+    }
+
+    if config.get_bool("check.behind").unwrap_or(true) && snapshot.behind != 0 {
+        let upstream = snapshot.upstream.as_deref().unwrap_or("upstream");
+        risks.push(format!("{} behind `{upstream}`", snapshot.behind));
+    }
+
+    if config.get_bool("check.conflicts").unwrap_or(true) && snapshot.conflicts.any() {
+        risks.push(format!(
+            "{} unresolved conflicts",
+            snapshot.conflicts.total()
+        ));
+    }
+
+    // rebases detach HEAD on purpose
+    if config.get_bool("check.detached").unwrap_or(true)
+        && snapshot.commit.is_some()
+        && snapshot.branch.is_none()
+        && snapshot.operation.is_none()
+    {
+        risks.push("detached HEAD".to_owned());
+    }
+
+    risks
+}
@@ -0,0 +1,470 @@
+use std::{env, ffi::OsString, path::PathBuf};
+
+use crate::repo::{Segment, Segments, Severity, Theme};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Print the prompt for the repo at `path`, the default.
+    Prompt,
+    /// Summarize the history log.
+    History,
+    /// Print a rollup over the repos of the workspace `name`.
+    Workspace,
+    /// Explain the environment and the state of the repo at `path`.
+    Doctor,
+    /// A read-only dashboard of the repo at `path` which refreshes on changes.
+    Tui,
+    /// Exit non-zero if committing in the repo at `path` would be risky by the `check.*` policy.
+    Check,
+    /// `hooks install`, install hooks into the repo at `path` which tell the cache about changes
+    /// made outside the shell.
+    Hooks,
+    /// `init <shell>`, print shell code putting the prompt into the prompt of `shell`.
+    Init,
+    /// Print every built-in theme across representative states to compare them.
+    Gallery,
+}
+
+impl Command {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "history" => Some(Self::History),
+            "workspace" => Some(Self::Workspace),
+            "doctor" => Some(Self::Doctor),
+            "hooks" => Some(Self::Hooks),
+            "check" => Some(Self::Check),
+            "tui" => Some(Self::Tui),
+            "init" => Some(Self::Init),
+            "gallery" => Some(Self::Gallery),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    /// The full prompt.
+    Prompt,
+    /// A single symbol for the most severe state.
+    Symbol,
+    /// Only the terminal title sequence.
+    Title,
+    /// Tab separated values, see `output::tsv`.
+    Tsv,
+    /// The built-in layout without any escapes and in a stable grammar for scripts and editors,
+    /// see `output::plain`.
+    Plain,
+    /// The values of `tsv` as shell variables to `eval`, see `output::env`.
+    Env,
+    /// The raw snapshot as JSON, see `output::json`.
+    Json,
+    /// The prompt for starship's `custom` module, see `output::for_starship`.
+    ///
+    /// Styles are `[text](style)` tokens and there is no trailing newline. Exits 0 with the
+    /// prompt in a repo and 1 without printing anything outside of one or if the status failed.
+    Starship,
+    /// The prompt styled by `#[fg=yellow,bold]` directives for tmux's `status-right`, e.g.
+    /// `#(epb-prompt-git --output=tmux "#{pane_current_path}")`.
+    Tmux,
+    /// The prompt as an HTML fragment, see `output::html` and `output::stylesheet`.
+    Html,
+    /// The prompt styled by zsh's `%F{3}` and `%B` prompt escapes, for `PROMPT` as is and for
+    /// themes post-processing the prompt, e.g. `PROMPT='$(epb-prompt-git --output=zsh) %# '`.
+    Zsh,
+    /// What bash's `__git_ps1` prints, a drop-in for existing `PS1` setups, see `output::ps1`.
+    Ps1,
+    /// The built-in layout split in two lines, the head and upstream for the left prompt and
+    /// anything else for the right one, e.g. zsh's `PROMPT` and `RPROMPT`, separated by NUL
+    /// instead with `--null`, see `repo::RepoSnapshot::render_split`.
+    Split,
+}
+
+impl Output {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "prompt" => Some(Self::Prompt),
+            "symbol" => Some(Self::Symbol),
+            "title" => Some(Self::Title),
+            "tsv" => Some(Self::Tsv),
+            "plain" => Some(Self::Plain),
+            "env" => Some(Self::Env),
+            "json" => Some(Self::Json),
+            "starship" => Some(Self::Starship),
+            "tmux" => Some(Self::Tmux),
+            "html" => Some(Self::Html),
+            "zsh" => Some(Self::Zsh),
+            "ps1" => Some(Self::Ps1),
+            "split" => Some(Self::Split),
+            _ => None,
+        }
+    }
+}
+
+/// When to color the output, see `--color`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Only if stdout is a terminal.
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(Self::Auto),
+            "always" => Some(Self::Always),
+            "never" => Some(Self::Never),
+            _ => None,
+        }
+    }
+}
+
+/// A shell the prompt is embedded into, see `output::for_shell`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    /// Escapes wrapped in `%{ %}`, `%` escaped.
+    Zsh,
+    /// Escapes wrapped in `\[ \]`, backslashes, `$` and backticks escaped for `PS1` with
+    /// `promptvars` set, the default.
+    Bash,
+    /// Escapes as they are, fish measures the prompt itself.
+    Fish,
+}
+
+impl Shell {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "zsh" => Some(Self::Zsh),
+            "bash" => Some(Self::Bash),
+            "fish" => Some(Self::Fish),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Args {
+    pub command: Command,
+    pub path: Option<PathBuf>,
+    /// Work in the directory open as this fd instead of a path, unix only.
+    pub dir_fd: Option<u32>,
+    pub name: Option<String>,
+    pub output: Output,
+    pub set_title: bool,
+    /// Also print `output::hash` of the state, on a second line or as a JSON field.
+    pub emit_hash: bool,
+    /// Also print the terminal cells the prompt takes on a line after it, see `output::width`.
+    pub emit_width: bool,
+    /// Show this many characters of detached commits' hashes instead of `hash.length`.
+    pub hash_len: Option<usize>,
+    /// Shorten the prompt to at most this many cells, see `output::fit`.
+    pub max_width: Option<usize>,
+    /// Lay the prompt out by this template instead of `format`, see `repo::Template`.
+    pub format: Option<String>,
+    /// Draw the prompt in this theme instead of `theme`.
+    pub theme: Option<Theme>,
+    /// Print nothing but ASCII, see `ascii`.
+    pub ascii: bool,
+    /// Overrides `NO_COLOR` and `CLICOLOR_FORCE`, colored by default as shells capture prompts
+    /// rather than print them to a terminal.
+    pub color: Option<ColorChoice>,
+    /// `--set key=value` overrides of the config, in order.
+    pub set: Vec<(String, String)>,
+    /// Mark escapes for the prompt of this shell instead of printing them raw, for `init` the shell
+    /// to print code for.
+    pub shell: Option<Shell>,
+    /// Parts left out of the prompt by `--no-<part>`, see `hidden_segment`.
+    pub hidden: Segments,
+    /// Print nothing unless the repo is at least this severe.
+    pub min_severity: Severity,
+    /// Experimental, show a placeholder on the terminal while the status is slow, see
+    /// `progressive::with_placeholder`.
+    pub progressive: bool,
+    /// Stream NUL terminated `<path>\0<output>\0` records instead of a summary, for
+    /// `--output=split` separate the sides by NUL.
+    pub null: bool,
+    /// Also show the subject of the HEAD commit and the latest stash, see `head.subject` and
+    /// `stash.subject`.
+    pub verbose: bool,
+    /// Count ignored files, see `status.ignored`.
+    pub show_ignored: bool,
+    /// Never spawn git or write anything, see `native::get_snapshot`.
+    pub sandbox: bool,
+    pub debug: bool,
+}
+
+// the part of the prompt `--no-<name>` leaves out
+fn hidden_segment(name: &str) -> Option<Segment> {
+    match name {
+        "remote" => Some(Segment::Remote),
+        "divergence" => Some(Segment::Div),
+        "push" => Some(Segment::Push),
+        "push-divergence" => Some(Segment::PushDiv),
+        "fallback" => Some(Segment::Fallback),
+        "fallback-divergence" => Some(Segment::FallbackDiv),
+        "age" => Some(Segment::Age),
+        "commits" => Some(Segment::Commits),
+        "stacked" => Some(Segment::Stacked),
+        "tag" => Some(Segment::Tag),
+        "worktree" => Some(Segment::Worktree),
+        "stash" => Some(Segment::Stash),
+        "conflicts" => Some(Segment::Conflicts),
+        "working-tree" => Some(Segment::WorkingTree),
+        "index" => Some(Segment::Index),
+        "submodules" => Some(Segment::Submodules),
+        "subject" => Some(Segment::Subject),
+        _ => None,
+    }
+}
+
+// `--flag=value` or `--flag value`
+fn flag_value(
+    inline: Option<&str>,
+    args: &mut impl Iterator<Item = OsString>,
+    flag: &str,
+) -> Result<String, String> {
+    match inline {
+        Some(value) => Ok(value.to_owned()),
+        None => args
+            .next()
+            .and_then(|value| value.into_string().ok())
+            .ok_or_else(|| format!("missing value for `{flag}`")),
+    }
+}
+
+impl Args {
+    pub fn parse() -> Result<Self, String> {
+        Self::parse_from(env::args_os().skip(1))
+    }
+
+    // epb-prompt-git [<path>]
+    //                [--output=prompt|symbol|title|tsv|env|plain|json|starship|tmux|html|zsh|ps1
+    //                |split]
+    //                [--null] [--set-title] [--emit-hash] [--emit-width] [--max-width=<cells>]
+    //                [--min-severity=clean|ahead|dirty|behind|conflict]
+    //                [--progressive] [--verbose] [--show-ignored] [--dir-fd=<fd>] [--sandbox]
+    //                [--shell=zsh|bash|fish] [--format=<template>] [--theme=default|icons]
+    //                [--hash-len=<chars>]
+    //                [--ascii] [--color=auto|always|never] [--set <key>=<value>]... [--debug]
+    //                [--no-remote] [--no-divergence] [--no-push] [--no-push-divergence]
+    //                [--no-fallback] [--no-fallback-divergence] [--no-age] [--no-commits]
+    //                [--no-stacked] [--no-tag] [--no-worktree] [--no-stash] [--no-conflicts]
+    //                [--no-working-tree] [--no-index] [--no-submodules] [--no-subject]
+    // epb-prompt-git history [--debug]
+    // epb-prompt-git doctor [<path>] [--debug]
+    // epb-prompt-git check [<path>] [--debug]
+    // epb-prompt-git tui [<path>] [--debug]
+    // epb-prompt-git hooks install [<path>] [--debug]
+    // epb-prompt-git init zsh|bash|fish
+    // epb-prompt-git gallery [--output=prompt|html] [--ascii]
+    // epb-prompt-git workspace <name> [-z|--null]
+    //                [--output=prompt|symbol|tsv|env|plain|json|starship|tmux|html|zsh|ps1|split]
+    //                [--debug]
+    //
+    // a first positional matching a command name is the command, use `./<name>` for a directory
+    // which happens to share its name
+    pub fn parse_from(args: impl IntoIterator<Item = OsString>) -> Result<Self, String> {
+        let mut this = Self {
+            command: Command::Prompt,
+            path: None,
+            dir_fd: None,
+            name: None,
+            output: Output::Prompt,
+            set_title: false,
+            emit_hash: false,
+            emit_width: false,
+            max_width: None,
+            hash_len: None,
+            format: None,
+            theme: None,
+            ascii: false,
+            color: None,
+            set: vec![],
+            shell: None,
+            hidden: Segments::default(),
+            min_severity: Severity::Clean,
+            progressive: false,
+            null: false,
+            verbose: false,
+            show_ignored: false,
+            sandbox: false,
+            debug: false,
+        };
+
+        let mut args = args.into_iter();
+        let mut positionals = vec![];
+        while let Some(arg) = args.next() {
+            match arg.to_str() {
+                Some("-z") => this.null = true,
+                Some(flag) if flag.starts_with("--") => {
+                    let (flag, inline) = match flag.split_once('=') {
+                        Some((flag, value)) => (flag, Some(value)),
+                        None => (flag, None),
+                    };
+
+                    match flag {
+                        "--debug" => this.debug = true,
+                        "--verbose" => this.verbose = true,
+                        "--show-ignored" => this.show_ignored = true,
+                        "--sandbox" => this.sandbox = true,
+                        "--null" => this.null = true,
+                        "--set-title" => this.set_title = true,
+                        "--emit-hash" => this.emit_hash = true,
+                        "--emit-width" => this.emit_width = true,
+                        "--ascii" => this.ascii = true,
+                        "--progressive" => this.progressive = true,
+                        "--output" => {
+                            let value = flag_value(inline, &mut args, flag)?;
+                            this.output = Output::from_name(&value)
+                                .ok_or_else(|| format!("unknown output `{value}`"))?;
+                        }
+                        "--format" => this.format = Some(flag_value(inline, &mut args, flag)?),
+                        "--set" => {
+                            let value = flag_value(inline, &mut args, flag)?;
+                            let (key, value) = value
+                                .split_once('=')
+                                .ok_or_else(|| format!("`--set {value}` is not `key=value`"))?;
+                            this.set.push((key.to_owned(), value.to_owned()));
+                        }
+                        "--theme" => {
+                            let value = flag_value(inline, &mut args, flag)?;
+                            this.theme = Some(
+                                Theme::from_name(&value)
+                                    .ok_or_else(|| format!("unknown theme `{value}`"))?,
+                            );
+                        }
+                        "--max-width" => {
+                            let value = flag_value(inline, &mut args, flag)?;
+                            this.max_width = Some(
+                                value
+                                    .parse()
+                                    .map_err(|_| format!("invalid width `{value}`"))?,
+                            );
+                        }
+                        "--hash-len" => {
+                            let value = flag_value(inline, &mut args, flag)?;
+                            this.hash_len = Some(
+                                value
+                                    .parse()
+                                    .ok()
+                                    .filter(|&len| len > 0)
+                                    .ok_or_else(|| format!("invalid hash length `{value}`"))?,
+                            );
+                        }
+                        "--dir-fd" => {
+                            let value = flag_value(inline, &mut args, flag)?;
+                            this.dir_fd =
+                                Some(value.parse().map_err(|_| format!("invalid fd `{value}`"))?);
+                        }
+                        "--color" => {
+                            let value = flag_value(inline, &mut args, flag)?;
+                            this.color = Some(
+                                ColorChoice::from_name(&value)
+                                    .ok_or_else(|| format!("unknown color choice `{value}`"))?,
+                            );
+                        }
+                        "--shell" => {
+                            let value = flag_value(inline, &mut args, flag)?;
+                            this.shell = Some(
+                                Shell::from_name(&value)
+                                    .ok_or_else(|| format!("unknown shell `{value}`"))?,
+                            );
+                        }
+                        "--min-severity" => {
+                            let value = flag_value(inline, &mut args, flag)?;
+                            this.min_severity = Severity::from_name(&value)
+                                .ok_or_else(|| format!("unknown severity `{value}`"))?;
+                        }
+                        _ => match flag.strip_prefix("--no-").and_then(hidden_segment) {
+                            Some(segment) if inline.is_none() => this.hidden.insert(segment),
+                            _ => return Err(format!("unknown flag `{flag}`")),
+                        },
+                    }
+                }
+                Some(name) if positionals.is_empty() && this.command == Command::Prompt => {
+                    match Command::from_name(name) {
+                        Some(command) => this.command = command,
+                        None => positionals.push(arg),
+                    }
+                }
+                _ => positionals.push(arg),
+            }
+        }
+
+        let mut positionals = positionals.into_iter();
+        match this.command {
+            Command::Prompt | Command::Doctor | Command::Check | Command::Tui => {
+                this.path = positionals.next().map(PathBuf::from)
+            }
+            Command::History => {}
+            Command::Gallery => {
+                if !matches!(this.output, Output::Prompt | Output::Html) {
+                    return Err("the gallery is only rendered as `prompt` or `html`".to_owned());
+                }
+            }
+            Command::Hooks => {
+                match positionals.next() {
+                    Some(action) if action == "install" => {}
+                    Some(action) => {
+                        return Err(format!(
+                            "unknown hooks action `{}`",
+                            action.to_string_lossy()
+                        ))
+                    }
+                    None => return Err("missing hooks action, `install`".to_owned()),
+                }
+
+                this.path = positionals.next().map(PathBuf::from);
+            }
+            Command::Init => {
+                let shell = positionals
+                    .next()
+                    .ok_or("missing shell, `zsh`, `bash` or `fish`")?;
+                let shell = shell.to_string_lossy();
+                this.shell = Some(
+                    Shell::from_name(&shell).ok_or_else(|| format!("unknown shell `{shell}`"))?,
+                );
+            }
+            Command::Workspace => {
+                let name = positionals.next().ok_or("missing workspace name")?;
+                this.name = Some(name.to_string_lossy().into_owned());
+            }
+        }
+
+        if let Some(arg) = positionals.next() {
+            return Err(format!("unexpected argument `{}`", arg.to_string_lossy()));
+        }
+
+        if this.sandbox && this.command != Command::Prompt {
+            return Err("`--sandbox` only applies to the prompt".to_owned());
+        }
+
+        if this.output == Output::Zsh && this.shell.is_some() {
+            return Err(
+                "`--output=zsh` is already escaped for zsh, leave out `--shell`".to_owned(),
+            );
+        }
+
+        if this.emit_width && this.output != Output::Prompt {
+            return Err("`--emit-width` only applies to `--output=prompt`".to_owned());
+        }
+
+        if this.max_width.is_some() && this.output != Output::Prompt {
+            return Err("`--max-width` only applies to `--output=prompt`".to_owned());
+        }
+
+        if this.output == Output::Split && this.format.is_some() {
+            return Err(
+                "`--format` has no sides to split, `--output=split` needs the built-in layout"
+                    .to_owned(),
+            );
+        }
+
+        if this.dir_fd.is_some() && this.path.is_some() {
+            return Err("`--dir-fd` and a path are exclusive".to_owned());
+        }
+
+        Ok(this)
+    }
+}
@@ -0,0 +1,366 @@
+use std::{collections::BTreeMap, error::Error, fs, io, path::Path};
+
+use crate::dirs;
+
+mod parse;
+pub use parse::{Kind, Value};
+
+/// The config file of a single clone in its git dir, it overrides the one in the config dir.
+pub const REPO_FILE: &str = "epb-prompt.toml";
+
+/// The config format version written by this release, bump this when adding a [`Rename`].
+pub const VERSION: i64 = 1;
+
+/// A key which was renamed in config version `since`, configs declaring an older version (or
+/// none at all) are migrated on load.
+struct Rename {
+    since: i64,
+    from: &'static str,
+    to: &'static str,
+}
+
+const RENAMES: &[Rename] = &[];
+
+/// Every key this release understands and its type, anything else is reported as unknown.
+const KEYS: &[(&str, Kind)] = &[
+    ("version", Kind::Int),
+    // print nothing at all in this repo, meant for `git config prompt.disable true`
+    ("disable", Kind::Bool),
+    // scan for untracked files, `git config prompt.untracked no` skips the scan in huge repos
+    ("status.untracked", Kind::Bool),
+    // use `git describe` style names for detached commits which are not exactly on a tag
+    ("detached.describe", Kind::Bool),
+    // how many characters of a detached commit's hash to show, 7 by default, also `--hash-len`
+    ("hash.length", Kind::Int),
+    // abbreviate the hash as git does instead, as `core.abbrev` says and long enough to be unique
+    ("hash.abbrev", Kind::Bool),
+    // always show the upstream branch name, even if it is the same as the local one
+    ("upstream.full", Kind::Bool),
+    // leave out `[origin/~][]` while level with an upstream of the same name
+    ("upstream.compact", Kind::Bool),
+    // show the subject of the HEAD commit, also enabled by `--verbose`
+    ("head.subject", Kind::Bool),
+    // mark the prompt for this many seconds after switching branches, so an async prompt can be
+    // told apart from a stale one, 0 disables this
+    ("head.switched", Kind::Int),
+    // show the subject of the latest stash and the branch it was made on if that is another one,
+    // also enabled by `--verbose`
+    ("stash.subject", Kind::Bool),
+    // mark repos which have not been fetched for this many seconds, e.g. `⟳6d`, 0 disables this
+    ("fetch.stale", Kind::Int),
+    // show whether HEAD is signed and the signature verifies, a green or red lock
+    ("head.signature", Kind::Bool),
+    // show the time since HEAD was committed, e.g. `3h`
+    ("head.age", Kind::Bool),
+    // the branch to measure branch age and commits against, defaults to `origin/HEAD`
+    ("branch.base", Kind::Str),
+    // cut branch names, local and upstream, to this many characters, the `…` marking them as cut
+    // included, 0 never cuts them
+    ("branch.length", Kind::Int),
+    // where the names lose their characters, `end` or `middle`
+    ("branch.ellipsis", Kind::Str),
+    // show the tag a branch is exactly on, e.g. `main[origin/~][] [v2.0.0]`
+    ("branch.tag", Kind::Bool),
+    // show an issue tracker id in the branch name as its own segment, e.g. `PROJ-123:login` for
+    // `PROJ-123-login`
+    ("ticket.enabled", Kind::Bool),
+    // what ids look like, a small regex subset with an optional group, defaults to
+    // `[A-Z][A-Z0-9]+-\d+`
+    ("ticket.pattern", Kind::Str),
+    // link the id to this url with `{ticket}` replaced by the id
+    ("ticket.url", Kind::Str),
+    // link the branch and detached commits to their pages on GitHub or GitLab with OSC 8, for
+    // upstreams on `github.*` or `gitlab.*` hosts
+    ("links.enabled", Kind::Bool),
+    // show the time since the branch diverged from the base branch
+    ("branch.age", Kind::Bool),
+    // show the number of commits on the branch which are not on the base branch
+    ("branch.commits", Kind::Bool),
+    // count branches stacked on top of the current one, e.g. `≡2`
+    ("stack.enabled", Kind::Bool),
+    // which branches make up stacks as a `for-each-ref` pattern, defaults to `refs/heads`, e.g.
+    // `refs/heads/stack/`
+    ("stack.pattern", Kind::Str),
+    // also show the divergence from where `git push` pushes to if that is not the upstream
+    ("branch.push", Kind::Bool),
+    // show lines inserted and deleted in the working tree and index, e.g. `+120 −34`
+    ("diff.stat", Kind::Bool),
+    // count files hidden from status by `update-index --skip-worktree` or `--assume-unchanged`,
+    // e.g. `⊘2`, sparse checkouts mark everything outside of them as skip-worktree
+    ("status.hidden", Kind::Bool),
+    // count untracked directories apart from files as `%/N`, a single one may hide thousands of
+    // files
+    ("untracked.dirs", Kind::Bool),
+    // count ignored files, e.g. to audit bloated build directories, also enabled by
+    // `--show-ignored`
+    ("status.ignored", Kind::Bool),
+    // compare branches without an upstream to the base branch instead, e.g. `[-:origin/main][2]`
+    ("branch.fallback", Kind::Bool),
+    // lay the prompt out like `{branch}{remote:[{}]}{div} :: w[{wt}] i[{idx}]`, see
+    // `repo::Template`, also `--format`
+    ("format", Kind::Str),
+    // the segments of the built-in layout in another order, e.g. `["stash", "branch", "remote",
+    // "div", "wt", "idx"]`, named as in `format`, which wins if both are set
+    ("order", Kind::List),
+    // what to do about the divergence from the upstream, `glyph` for `⇣`, `⇡` or `⛔` and
+    // `text` for `needs pull`, `needs push` or `push blocked: protected`, unset shows none
+    ("hint.style", Kind::Str),
+    // the built-in look, `default` or `icons` for Nerd Font icons, see `repo::Theme`, also
+    // `--theme`
+    ("theme", Kind::Str),
+    // the background of the terminal the colors are tuned for, `dark` or `light`, or `auto` to
+    // tell by `$COLORFGBG`, which rxvt, Konsole and others export, dark if it is unset
+    ("background", Kind::Str),
+    // nothing but ASCII whatever the theme, e.g. for serial consoles, also `--ascii`
+    ("ascii", Kind::Bool),
+    // the glyphs in front of the commits ahead of and behind the upstream, `↑` and `↓` by
+    // default
+    ("symbol.ahead", Kind::Str),
+    ("symbol.behind", Kind::Str),
+    // how each part of the prompt looks, a color and or `bold` or `plain` for neither, keyed by
+    // the role as in the classes of `--output=html`, e.g. `color.branch-name = "green bold"`,
+    // colors are named, a palette index like `208` or 24-bit like `#ff8700` or `rgb(255,135,0)`
+    ("color.*", Kind::Str),
+    // counts above this are written as `99+` for a cap of 99
+    ("count.cap", Kind::Int),
+    // leave out counts of a kind of change up to this, by the `symbol.<change>` names, e.g.
+    // `count.untracked = 10`
+    ("count.*", Kind::Int),
+    // what goes between the head, the stash and the changes in place of `::`, e.g. `|` or `·`,
+    // empty for none
+    ("separator", Kind::Str),
+    // the glyphs of the counts of each kind of change, `+`, `~`, `-`, `*`, `&`, `?`, `@`, `%` and
+    // `%/` by default
+    ("symbol.add", Kind::Str),
+    ("symbol.mod", Kind::Str),
+    ("symbol.del", Kind::Str),
+    ("symbol.ren", Kind::Str),
+    ("symbol.cpy", Kind::Str),
+    ("symbol.typ", Kind::Str),
+    ("symbol.sub", Kind::Str),
+    ("symbol.untracked", Kind::Str),
+    ("symbol.untracked_dirs", Kind::Str),
+    // the `s` of stashes, the `!` of conflicts and the arrows between the branches of a merge
+    // (`<-`) or rebase (`->`)
+    ("symbol.stash", Kind::Str),
+    ("symbol.conflict", Kind::Str),
+    ("symbol.merge", Kind::Str),
+    ("symbol.rebase", Kind::Str),
+    // break conflicts down by kind, e.g. `[!3: uu2 dd1]`
+    ("conflicts.detailed", Kind::Bool),
+    // milliseconds a full status may take before untracked files, diffstat and describe are skipped
+    // for a while, 0 disables this
+    ("adaptive.budget", Kind::Int),
+    // branches `check` refuses to commit on, the hint tells pushing to them is blocked
+    ("check.protected", Kind::List),
+    // whether `check` refuses to commit while behind the upstream, with unresolved conflicts or on
+    // a detached HEAD, all default to true
+    ("check.behind", Kind::Bool),
+    ("check.conflicts", Kind::Bool),
+    ("check.detached", Kind::Bool),
+    // a command printing the CI status of the branch in `$EPB_PROMPT_GIT_BRANCH`, e.g. `success`,
+    // `failure` or `pending`, its output is cached for `ci.ttl` seconds (300) and it is killed
+    // after `ci.timeout` milliseconds (1000)
+    ("ci.command", Kind::Str),
+    ("ci.ttl", Kind::Int),
+    ("ci.timeout", Kind::Int),
+    // a command printing the open pull request for the branch in `$EPB_PROMPT_GIT_BRANCH` if there
+    // is one, e.g. `gh pr view --json number --jq .number`, or `curl` against the forge API with a
+    // token, cached and killed like `ci.command`
+    ("pr.command", Kind::Str),
+    ("pr.ttl", Kind::Int),
+    ("pr.timeout", Kind::Int),
+    // append every rendered prompt to the history log in the state dir
+    ("history.enabled", Kind::Bool),
+    // repo paths making up the workspace `<name>`
+    ("workspace.*", Kind::List),
+];
+
+/// `git config prompt.<name>` names which are not simply `prompt.` and the key, git lowercases the
+/// last part of names, e.g. `prompt.baseBranch` is `prompt.basebranch`.
+const GIT_ALIASES: &[(&str, &str)] = &[
+    ("basebranch", "branch.base"),
+    ("untracked", "status.untracked"),
+];
+
+// `*` matches the rest of the key
+fn is_key_match(pattern: &str, key: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => key.len() > prefix.len() && key.starts_with(prefix),
+        None => pattern == key,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Config {
+    values: BTreeMap<String, Value>,
+}
+
+impl Config {
+    /// Loads `config.toml` from the config dir, a missing file is an empty config.
+    pub fn load(warnings: &mut Vec<String>) -> Result<Self, Box<dyn Error>> {
+        match dirs::config_dir() {
+            Some(dir) => Self::load_from(&dir.join("config.toml"), warnings),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn load_from(path: &Path, warnings: &mut Vec<String>) -> Result<Self, Box<dyn Error>> {
+        let src = match fs::read_to_string(path) {
+            Ok(src) => src,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let values = parse::parse(&src).map_err(|err| format!("{}: {err}", path.display()))?;
+        Ok(Self::migrate(values, warnings))
+    }
+
+    /// Overrides the keys set in `other`, e.g. with the [`REPO_FILE`] of a clone.
+    pub fn merge(&mut self, other: Self) {
+        self.values.extend(other.values);
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.values.get(key) {
+            Some(&Value::Bool(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_int(&self, key: &str) -> Option<i64> {
+        match self.values.get(key) {
+            Some(&Value::Int(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        match self.values.get(key) {
+            Some(Value::Str(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_list(&self, key: &str) -> Option<&[Value]> {
+        match self.values.get(key) {
+            Some(Value::List(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Overrides `key` for this run, e.g. from a command line flag.
+    pub fn set(&mut self, key: &str, value: Value) {
+        self.values.insert(key.to_owned(), value);
+    }
+
+    /// Overrides `key` with `value` typed as the key is, e.g. from `--set key=value`.
+    ///
+    /// Strings are taken as is, anything else is written as in the config file, e.g. `true`, `5`
+    /// or `["main", "master"]`.
+    pub fn set_str(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let &(_, kind) = KEYS
+            .iter()
+            .find(|&&(known, _)| is_key_match(known, key))
+            .ok_or_else(|| format!("unknown key `{key}`"))?;
+
+        let value = match kind {
+            Kind::Str => Value::Str(value.to_owned()),
+            _ => parse::parse(&format!("value = {value}"))
+                .ok()
+                .and_then(|mut values| values.remove("value"))
+                .filter(|value| value.kind() == kind)
+                .ok_or_else(|| format!("`{key}` must be {kind}, got `{value}`"))?,
+        };
+
+        self.set(key, value);
+        Ok(())
+    }
+
+    /// Overrides keys with the `prompt.*` settings of a repo as listed by `git config -z
+    /// --get-regexp`, e.g. `prompt.head.subject` for `head.subject`, see [`GIT_ALIASES`].
+    ///
+    /// Booleans are written as git does, e.g. `yes`, `off` or no value at all for true.
+    pub fn apply_git(&mut self, listing: &str, warnings: &mut Vec<String>) {
+        // `<name>\n<value>\0`, a name without value has no `\n`
+        for entry in listing.split_terminator('\0') {
+            let (name, value) = match entry.split_once('\n') {
+                Some((name, value)) => (name, Some(value)),
+                None => (entry, None),
+            };
+
+            let Some(key) = name.strip_prefix("prompt.") else {
+                continue;
+            };
+            let key = GIT_ALIASES
+                .iter()
+                .find(|&&(alias, _)| alias == key)
+                .map_or(key, |&(_, key)| key);
+
+            let is_bool = KEYS
+                .iter()
+                .any(|&(known, kind)| kind == Kind::Bool && is_key_match(known, key));
+            let value = match value {
+                Some(value) if !is_bool => value.to_owned(),
+                None => "true".to_owned(),
+                Some(value) => match value.to_ascii_lowercase().as_str() {
+                    "true" | "yes" | "on" | "1" => "true".to_owned(),
+                    "false" | "no" | "off" | "0" | "" => "false".to_owned(),
+                    _ => value.to_owned(),
+                },
+            };
+
+            if let Err(err) = self.set_str(key, &value) {
+                warnings.push(format!("git config: {name}: {err}"));
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.values.iter().map(|(key, value)| (key.as_str(), value))
+    }
+
+    fn migrate(mut values: BTreeMap<String, Value>, warnings: &mut Vec<String>) -> Self {
+        // configs from before versioning are version 1
+        let version = match values.get("version") {
+            Some(&Value::Int(version)) => version,
+            // reported with the other type mismatches below
+            Some(_) => VERSION,
+            None => 1,
+        };
+
+        if version > VERSION {
+            warnings.push(format!(
+                "config: version {version} is newer than supported version {VERSION}"
+            ));
+        }
+
+        for rename in RENAMES.iter().filter(|rename| version < rename.since) {
+            if let Some(value) = values.remove(rename.from) {
+                warnings.push(format!(
+                    "config: `{}` was renamed to `{}` in version {}",
+                    rename.from, rename.to, rename.since
+                ));
+
+                // an explicitly set new key wins over the migrated one
+                values.entry(rename.to.to_owned()).or_insert(value);
+            }
+        }
+
+        values.retain(|key, value| {
+            match KEYS.iter().find(|&&(known, _)| is_key_match(known, key)) {
+                Some(&(_, kind)) if value.kind() != kind => {
+                    warnings.push(format!("config: `{key}` must be {kind}, got {value}"));
+                    false
+                }
+                Some(_) => true,
+                None => {
+                    warnings.push(format!("config: unknown key `{key}`"));
+                    true
+                }
+            }
+        });
+
+        Self { values }
+    }
+}
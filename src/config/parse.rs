@@ -0,0 +1,340 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::{Debug, Display},
+    iter::Peekable,
+    str::Chars,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Bool(bool),
+    Int(i64),
+    Str(String),
+    List(Vec<Value>),
+}
+
+impl Value {
+    pub fn kind(&self) -> Kind {
+        match self {
+            Value::Bool(_) => Kind::Bool,
+            Value::Int(_) => Kind::Int,
+            Value::Str(_) => Kind::Str,
+            Value::List(_) => Kind::List,
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Bool(b) => Display::fmt(b, f),
+            Value::Int(i) => Display::fmt(i, f),
+            Value::Str(s) => Debug::fmt(s, f),
+            Value::List(list) => {
+                f.write_str("[")?;
+                for (idx, value) in list.iter().enumerate() {
+                    if idx != 0 {
+                        f.write_str(", ")?;
+                    }
+                    Display::fmt(value, f)?;
+                }
+                f.write_str("]")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Bool,
+    Int,
+    Str,
+    List,
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Kind::Bool => "a boolean",
+            Kind::Int => "an integer",
+            Kind::Str => "a string",
+            Kind::List => "an array",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    line: usize,
+    message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for ParseError {}
+
+// a toml subset, enough for flat settings:
+// - `[table]` and `[table.sub]` headers
+// - `key = value` and `dotted.key = value` with bare keys
+// - basic and literal strings, integers, booleans and (nested) arrays
+// - `#` comments
+//
+// keys are flattened into their full dotted path, `[a] b = 1` becomes `a.b`
+pub fn parse(src: &str) -> Result<BTreeMap<String, Value>, ParseError> {
+    let mut parser = Parser {
+        chars: src.chars().peekable(),
+        line: 1,
+    };
+
+    let mut table = String::new();
+    let mut values = BTreeMap::new();
+
+    loop {
+        parser.skip_trivia();
+
+        match parser.chars.peek() {
+            None => break,
+            Some('[') => {
+                parser.chars.next();
+                parser.skip_ws();
+                table = parser.key()?;
+                parser.skip_ws();
+                parser.expect(']')?;
+            }
+            Some(_) => {
+                let key = parser.key()?;
+                parser.skip_ws();
+                parser.expect('=')?;
+                parser.skip_ws();
+                let value = parser.value()?;
+
+                let key = if table.is_empty() {
+                    key
+                } else {
+                    format!("{table}.{key}")
+                };
+
+                if values.contains_key(&key) {
+                    return Err(parser.error(format!("duplicate key `{key}`")));
+                }
+                values.insert(key, value);
+            }
+        }
+
+        parser.expect_eol()?;
+    }
+
+    Ok(values)
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError {
+            line: self.line,
+            message: message.into(),
+        }
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c == Some('\n') {
+            self.line += 1;
+        }
+        c
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        match self.chars.peek().copied() {
+            Some(c) if c == expected => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!("expected `{expected}`, found `{c}`"))),
+            None => Err(self.error(format!("expected `{expected}`, found end of file"))),
+        }
+    }
+
+    fn skip_ws(&mut self) {
+        while let Some(' ' | '\t') = self.chars.peek() {
+            self.bump();
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if let Some('#') = self.chars.peek() {
+            while !matches!(self.chars.peek(), None | Some('\n')) {
+                self.bump();
+            }
+        }
+    }
+
+    // whitespace, newlines and comments
+    fn skip_trivia(&mut self) {
+        loop {
+            self.skip_ws();
+            self.skip_comment();
+            match self.chars.peek().copied() {
+                Some('\r' | '\n') => {
+                    self.bump();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn expect_eol(&mut self) -> Result<(), ParseError> {
+        self.skip_ws();
+        self.skip_comment();
+        if let Some('\r') = self.chars.peek() {
+            self.bump();
+        }
+
+        match self.chars.peek().copied() {
+            None => Ok(()),
+            Some('\n') => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!("expected end of line, found `{c}`"))),
+        }
+    }
+
+    fn key(&mut self) -> Result<String, ParseError> {
+        let mut key = String::new();
+
+        loop {
+            let start = key.len();
+            while let Some(&c) = self.chars.peek() {
+                if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                    key.push(c);
+                    self.bump();
+                } else {
+                    break;
+                }
+            }
+
+            if key.len() == start {
+                return Err(self.error("expected a bare key"));
+            }
+
+            self.skip_ws();
+            if let Some('.') = self.chars.peek() {
+                self.bump();
+                self.skip_ws();
+                key.push('.');
+            } else {
+                return Ok(key);
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, ParseError> {
+        match self.chars.peek().copied() {
+            Some('"') => self.basic_string().map(Value::Str),
+            Some('\'') => self.literal_string().map(Value::Str),
+            Some('[') => self.list().map(Value::List),
+            Some(_) => {
+                let mut word = String::new();
+                while let Some(&c) = self.chars.peek() {
+                    if c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '+' {
+                        word.push(c);
+                        self.bump();
+                    } else {
+                        break;
+                    }
+                }
+
+                match word.as_str() {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    "" => Err(self.error("expected a value")),
+                    _ => word
+                        .replace('_', "")
+                        .parse()
+                        .map(Value::Int)
+                        .map_err(|_| self.error(format!("invalid value `{word}`"))),
+                }
+            }
+            None => Err(self.error("expected a value, found end of file")),
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, ParseError> {
+        self.expect('"')?;
+        let mut string = String::new();
+
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(string),
+                Some('\\') => match self.bump() {
+                    Some('\\') => string.push('\\'),
+                    Some('"') => string.push('"'),
+                    Some('n') => string.push('\n'),
+                    Some('t') => string.push('\t'),
+                    Some('e') => string.push('\x1b'),
+                    Some(c @ ('u' | 'U')) => {
+                        let len = if c == 'u' { 4 } else { 8 };
+                        let hex: String = (0..len).filter_map(|_| self.bump()).collect();
+                        let c = u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| self.error(format!("invalid unicode escape `{hex}`")))?;
+                        string.push(c);
+                    }
+                    Some(c) => return Err(self.error(format!("invalid escape `\\{c}`"))),
+                    None => return Err(self.error("unterminated string")),
+                },
+                Some('\n') | None => return Err(self.error("unterminated string")),
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, ParseError> {
+        self.expect('\'')?;
+        let mut string = String::new();
+
+        loop {
+            match self.bump() {
+                Some('\'') => return Ok(string),
+                Some('\n') | None => return Err(self.error("unterminated string")),
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    fn list(&mut self) -> Result<Vec<Value>, ParseError> {
+        self.expect('[')?;
+        let mut list = vec![];
+
+        loop {
+            self.skip_trivia();
+            if let Some(']') = self.chars.peek() {
+                self.bump();
+                return Ok(list);
+            }
+
+            list.push(self.value()?);
+
+            self.skip_trivia();
+            match self.chars.peek().copied() {
+                Some(',') => {
+                    self.bump();
+                }
+                Some(']') => {}
+                Some(c) => return Err(self.error(format!("expected `,` or `]`, found `{c}`"))),
+                None => return Err(self.error("unterminated array")),
+            }
+        }
+    }
+}
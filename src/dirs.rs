@@ -0,0 +1,119 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+const APP: &str = "epb-prompt-git";
+
+// the XDG spec says relative paths must be ignored
+fn env_dir(key: &str) -> Option<PathBuf> {
+    env::var_os(key)
+        .filter(|s| !s.is_empty())
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+}
+
+pub fn home_dir() -> Option<PathBuf> {
+    if cfg!(windows) {
+        env_dir("USERPROFILE")
+    } else {
+        env_dir("HOME")
+    }
+}
+
+fn xdg_or(key: &str, fallback: impl FnOnce(&Path) -> PathBuf) -> Option<PathBuf> {
+    env_dir(key).or_else(|| home_dir().map(|home| fallback(&home)))
+}
+
+/// Where the config file lives.
+///
+/// - linux: `$XDG_CONFIG_HOME` | `~/.config`
+/// - macos: `$XDG_CONFIG_HOME` | `~/Library/Application Support`
+/// - windows: `%APPDATA%`
+pub fn config_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        env_dir("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        xdg_or("XDG_CONFIG_HOME", |home| {
+            home.join("Library/Application Support")
+        })
+    } else {
+        xdg_or("XDG_CONFIG_HOME", |home| home.join(".config"))
+    };
+
+    base.map(|base| base.join(APP))
+}
+
+/// Where disposable data lives, anything in here may be removed at any time.
+///
+/// - linux: `$XDG_CACHE_HOME` | `~/.cache`
+/// - macos: `$XDG_CACHE_HOME` | `~/Library/Caches`
+/// - windows: `%LOCALAPPDATA%`
+pub fn cache_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        env_dir("LOCALAPPDATA")
+    } else if cfg!(target_os = "macos") {
+        xdg_or("XDG_CACHE_HOME", |home| home.join("Library/Caches"))
+    } else {
+        xdg_or("XDG_CACHE_HOME", |home| home.join(".cache"))
+    };
+
+    // windows has no separate cache location, keep it apart from state
+    if cfg!(windows) {
+        base.map(|base| base.join(APP).join("cache"))
+    } else {
+        base.map(|base| base.join(APP))
+    }
+}
+
+/// The file switching the prompt off while it exists, e.g. during incidents with slow filesystems,
+/// see [`crate::disabled`].
+pub fn disable_file() -> Option<PathBuf> {
+    cache_dir().map(|dir| dir.join("disable"))
+}
+
+/// Where persistent but non-essential data lives, like logs and history.
+///
+/// - linux: `$XDG_STATE_HOME` | `~/.local/state`
+/// - macos: `$XDG_STATE_HOME` | `~/Library/Application Support`
+/// - windows: `%LOCALAPPDATA%`
+pub fn state_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        env_dir("LOCALAPPDATA")
+    } else if cfg!(target_os = "macos") {
+        xdg_or("XDG_STATE_HOME", |home| {
+            home.join("Library/Application Support")
+        })
+    } else {
+        xdg_or("XDG_STATE_HOME", |home| home.join(".local/state"))
+    };
+
+    if cfg!(windows) {
+        base.map(|base| base.join(APP).join("state"))
+    } else {
+        base.map(|base| base.join(APP))
+    }
+}
+
+/// Where sockets and other per-session files live.
+///
+/// - linux: `$XDG_RUNTIME_DIR` | `$TMPDIR/epb-prompt-git-$USER`
+/// - macos: `$XDG_RUNTIME_DIR` | `$TMPDIR` (which is per user already)
+/// - windows: `%LOCALAPPDATA%`
+pub fn runtime_dir() -> Option<PathBuf> {
+    if cfg!(windows) {
+        return env_dir("LOCALAPPDATA").map(|base| base.join(APP).join("run"));
+    }
+
+    if let Some(base) = env_dir("XDG_RUNTIME_DIR") {
+        return Some(base.join(APP));
+    }
+
+    if cfg!(target_os = "macos") {
+        Some(env::temp_dir().join(APP))
+    } else {
+        // the shared tmp dir needs a per user name to not collide
+        let user = env::var("USER").ok()?;
+        Some(env::temp_dir().join(format!("{APP}-{user}")))
+    }
+}
@@ -0,0 +1,92 @@
+use std::{
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::{adaptive, cache, config, dirs, disabled, get_snapshot, ticket, util};
+
+/// Explains the environment and why the repo at `path` renders the way it does.
+pub fn report(out: &mut impl Write, path: &Path, config: &config::Config) -> io::Result<()> {
+    let version = util::git(path).arg("--version").output().map(|output| {
+        String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_owned()
+    });
+
+    match version {
+        Ok(version) => writeln!(out, "git:      {version}")?,
+        Err(err) => writeln!(out, "git:      not runnable, {err}")?,
+    }
+
+    writeln!(out, "config:   {:?}", dirs::config_dir())?;
+    writeln!(out, "cache:    {:?}", dirs::cache_dir())?;
+
+    if let Some(reason) = disabled() {
+        writeln!(out, "prompt:   disabled, {reason}")?;
+    } else if config.get_bool("disable").unwrap_or(false) {
+        writeln!(out, "prompt:   disabled in this repo, `disable` is set")?;
+    }
+
+    if let Some(Err(err)) = config.get_str("ticket.pattern").map(ticket::Pattern::parse) {
+        writeln!(out, "ticket:   `ticket.pattern` is invalid, {err}")?;
+    }
+
+    let Some(git_dir) = util::find_git_dir(path) else {
+        return writeln!(out, "repo:     none at {}", path.display());
+    };
+    writeln!(out, "repo:     {}", git_dir.display())?;
+
+    // data of a newer git, still available in `--output=json`
+    if let Ok(snapshot) = get_snapshot(path, config) {
+        for (key, value) in &snapshot.headers {
+            writeln!(
+                out,
+                "header:   `# {key} {value}` is not understood by this release"
+            )?;
+        }
+
+        for warning in &snapshot.warnings {
+            writeln!(out, "warning:  {warning}")?;
+        }
+    }
+
+    match cache::stamp(&git_dir) {
+        Some(stamp) => writeln!(
+            out,
+            "hooks:    last change {} ago",
+            util::fmt_age(util::unix_now().saturating_sub(stamp))
+        )?,
+        None => writeln!(out, "hooks:    none seen, see `hooks install`")?,
+    }
+
+    let Some(budget) = adaptive::budget(config) else {
+        return writeln!(out, "adaptive: disabled by `adaptive.budget = 0`");
+    };
+    writeln!(out, "budget:   {}ms", budget.as_millis())?;
+
+    let Some(latency) = adaptive::last(path) else {
+        return writeln!(out, "status:   not measured yet");
+    };
+
+    writeln!(
+        out,
+        "status:   {}ms, {} ago",
+        latency.elapsed.as_millis(),
+        util::fmt_age(util::unix_now().saturating_sub(latency.measured_at))
+    )?;
+
+    if adaptive::is_reduced(path, config) {
+        writeln!(
+            out,
+            "reduced:  status exceeded the budget, untracked files, diffstat and describe are \
+             skipped for another {}",
+            util::fmt_age(adaptive::retry_in(&latency))
+        )?;
+        writeln!(
+            out,
+            "          raise `adaptive.budget` or set it to 0 to never skip anything"
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,90 @@
+/// The web UI flavour of a remote host, they differ in where branch and commit pages live.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ForgeKind {
+    GitHub,
+    GitLab,
+}
+
+impl ForgeKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ForgeKind::GitHub => "github",
+            ForgeKind::GitLab => "gitlab",
+        }
+    }
+}
+
+/// The web UI of a remote on a forge, see `links.enabled`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Forge {
+    pub kind: ForgeKind,
+    /// The project page, e.g. `https://github.com/owner/repo`.
+    pub url: String,
+}
+
+// anything but unreserved characters and `/`, e.g. `#` in a branch name would start a fragment
+fn encode(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for b in path.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                out.push(b as char)
+            }
+            b => out.push_str(&format!("%{b:02X}")),
+        }
+    }
+    out
+}
+
+impl Forge {
+    /// The forge a remote url points at, `None` unless the host is `github.com`, `gitlab.com` or
+    /// a self-hosted `github.<domain>` or `gitlab.<domain>`.
+    ///
+    /// Understands `https://host/owner/repo.git`, `ssh://git@host:22/owner/repo.git` and the scp
+    /// like `git@host:owner/repo.git`.
+    pub fn from_url(url: &str) -> Option<Self> {
+        let (host, path) = match url.split_once("://") {
+            Some((_, rest)) => rest.split_once('/')?,
+            // scp like syntax has no scheme and a `:` before the first `/`
+            None => url
+                .split_once(':')
+                .filter(|(host, _)| !host.contains('/'))?,
+        };
+
+        let host = host.rsplit_once('@').map_or(host, |(_, host)| host);
+        let host = host.split_once(':').map_or(host, |(host, _)| host);
+        let kind = match host.split('.').next()? {
+            "github" => ForgeKind::GitHub,
+            "gitlab" => ForgeKind::GitLab,
+            _ => return None,
+        };
+
+        let path = path.trim_matches('/');
+        let path = path.strip_suffix(".git").unwrap_or(path);
+        // GitLab groups nest, anything needs at least an owner and a repo
+        if !path.contains('/') || !host.contains('.') {
+            return None;
+        }
+
+        Some(Self {
+            kind,
+            url: format!("https://{host}/{}", encode(path)),
+        })
+    }
+
+    pub fn branch_url(&self, branch: &str) -> String {
+        match self.kind {
+            ForgeKind::GitHub => format!("{}/tree/{}", self.url, encode(branch)),
+            ForgeKind::GitLab => format!("{}/-/tree/{}", self.url, encode(branch)),
+        }
+    }
+
+    pub fn commit_url(&self, commit: &str) -> String {
+        match self.kind {
+            ForgeKind::GitHub => format!("{}/commit/{}", self.url, encode(commit)),
+            ForgeKind::GitLab => format!("{}/-/commit/{}", self.url, encode(commit)),
+        }
+    }
+}
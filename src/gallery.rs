@@ -0,0 +1,130 @@
+use std::fmt::Write;
+
+use crate::repo::{
+    Change, ConflictKind, ConflictRef, Markup, Operation, RenderOptions, RepoSnapshot, Theme,
+};
+
+// made up rather than measured so the gallery is the same everywhere, e.g. in documentation
+fn states() -> Vec<(&'static str, RepoSnapshot)> {
+    let clean = RepoSnapshot {
+        commit: Some("5d1e9c0a3b7f2e8d4c6a1b9f0e3d7c2a8b4f6e1d".to_owned()),
+        branch: Some("main".to_owned()),
+        upstream: Some("origin/main".to_owned()),
+        ..Default::default()
+    };
+
+    let mut dirty = clean.clone();
+    dirty.working_tree[Change::Mod] = 2;
+    dirty.working_tree[Change::Untracked] = 1;
+
+    let mut staged = clean.clone();
+    staged.index[Change::Add] = 1;
+    staged.index[Change::Mod] = 3;
+
+    let mut conflict = clean.clone();
+    conflict.operation = Some(Operation {
+        kind: ConflictKind::Merge,
+        source: ConflictRef::branch("feature".to_owned()),
+        target: ConflictRef::branch("main".to_owned()),
+    });
+    conflict.conflicts.add(('U', 'U'));
+    conflict.conflicts.add(('U', 'U'));
+    conflict.working_tree[Change::Mod] = 1;
+
+    vec![
+        ("clean", clean.clone()),
+        (
+            "ahead",
+            RepoSnapshot {
+                ahead: 2,
+                ..clean.clone()
+            },
+        ),
+        (
+            "behind",
+            RepoSnapshot {
+                behind: 3,
+                ..clean.clone()
+            },
+        ),
+        (
+            "diverged",
+            RepoSnapshot {
+                ahead: 1,
+                behind: 4,
+                ..clean.clone()
+            },
+        ),
+        ("dirty", dirty),
+        ("staged", staged),
+        (
+            "stashed",
+            RepoSnapshot {
+                stash: 2,
+                ..clean.clone()
+            },
+        ),
+        (
+            "upstream gone",
+            RepoSnapshot {
+                upstream_gone: true,
+                ..clean.clone()
+            },
+        ),
+        (
+            "detached",
+            RepoSnapshot {
+                branch: None,
+                upstream: None,
+                tag: Some("v1.4.0".to_owned()),
+                ..clean.clone()
+            },
+        ),
+        ("merge conflict", conflict),
+        (
+            "no commits",
+            RepoSnapshot {
+                commit: None,
+                upstream: None,
+                ..clean
+            },
+        ),
+    ]
+}
+
+/// Every built-in theme rendering the same representative states, one aligned line each, see
+/// `gallery`.
+///
+/// With [`Markup::Html`] the lines are a single `<pre>` styled by `output::stylesheet`.
+pub fn render(options: &RenderOptions) -> String {
+    let states = states();
+    let width = Theme::ALL
+        .iter()
+        .map(|theme| theme.name().len())
+        .chain(states.iter().map(|(name, _)| name.len()))
+        .max()
+        .unwrap_or(0);
+
+    let mut out = String::new();
+    if options.markup == Markup::Html {
+        out.push_str("<pre class=\"epg\">");
+    }
+
+    for (i, theme) in Theme::ALL.into_iter().enumerate() {
+        if i != 0 {
+            out.push('\n');
+        }
+
+        let _ = writeln!(out, "{}", theme.name());
+        let options = RenderOptions { theme, ..*options };
+        for (name, snapshot) in &states {
+            let _ = writeln!(out, "  {name:width$}  {}", snapshot.render(&options));
+        }
+    }
+
+    if options.markup == Markup::Html {
+        out.push_str("</pre>");
+    }
+
+    out
+}
@@ -0,0 +1,113 @@
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{dirs, repo, util};
+
+// an append only log, one line per rendered prompt
+// <unix secs>\t<repo>\t<head>\t<working tree>\t<index>\t<conflicts>
+const FILE: &str = "history.tsv";
+
+// gaps between two prompts longer than this are time spent away, not on the branch
+const IDLE: u64 = 15 * 60;
+
+fn log_path() -> Option<PathBuf> {
+    dirs::state_dir().map(|dir| dir.join(FILE))
+}
+
+pub fn record(repo: &Path, prompt: &repo::Prompt) -> io::Result<()> {
+    let Some(path) = log_path() else {
+        return Ok(());
+    };
+
+    let repo = repo.to_string_lossy();
+    let head = prompt.head_name();
+
+    // don't corrupt the log, these are rare enough to not be worth escaping
+    if [&*repo, &*head]
+        .iter()
+        .any(|field| field.contains(['\t', '\n']))
+    {
+        return Ok(());
+    }
+
+    let now = util::unix_now();
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(
+        file,
+        "{now}\t{repo}\t{head}\t{}\t{}\t{}",
+        prompt.working_tree().map(repo::Changes::total).unwrap_or(0),
+        prompt.index().map(repo::Changes::total).unwrap_or(0),
+        prompt.conflicts(),
+    )
+}
+
+fn fmt_duration(secs: u64) -> String {
+    let (hours, minutes) = (secs / 3600, secs % 3600 / 60);
+    if hours != 0 {
+        format!("{hours}h{minutes:02}m")
+    } else {
+        format!("{minutes}m")
+    }
+}
+
+/// Writes the time spent per repo and branch, attributing the time between two consecutive
+/// prompts to the state of the first.
+pub fn summarize(out: &mut impl Write) -> io::Result<()> {
+    let content = match log_path().map(fs::read_to_string) {
+        Some(Ok(content)) => content,
+        Some(Err(err)) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+        _ => {
+            writeln!(
+                out,
+                "no history recorded, set `history.enabled = true` to record"
+            )?;
+            return Ok(());
+        }
+    };
+
+    let entries: Vec<_> = content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let time: u64 = fields.next()?.parse().ok()?;
+            Some((time, fields.next()?, fields.next()?))
+        })
+        .collect();
+
+    let mut repos: HashMap<&str, (u64, HashMap<&str, u64>)> = HashMap::new();
+    for pair in entries.windows(2) {
+        let ((time, repo, head), (next, _, _)) = (pair[0], pair[1]);
+        let spent = next.saturating_sub(time);
+        if spent > IDLE {
+            continue;
+        }
+
+        let (total, heads) = repos.entry(repo).or_default();
+        *total += spent;
+        *heads.entry(head).or_default() += spent;
+    }
+
+    let mut repos: Vec<_> = repos.into_iter().collect();
+    repos.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then(a.0.cmp(b.0)));
+
+    for (repo, (total, heads)) in repos {
+        writeln!(out, "{:>8}  {repo}", fmt_duration(total))?;
+
+        let mut heads: Vec<_> = heads.into_iter().collect();
+        heads.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        for (head, spent) in heads {
+            writeln!(out, "{:>10}  {head}", fmt_duration(spent))?;
+        }
+    }
+
+    Ok(())
+}
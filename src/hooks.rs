@@ -0,0 +1,79 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::{cache, util};
+
+// commits, checkouts, merges and rebases, i.e. everything that moves HEAD from outside the shell
+const HOOKS: [&str; 4] = ["post-commit", "post-checkout", "post-merge", "post-rewrite"];
+
+// identifies hooks written by us, anything else is left alone
+const MARKER: &str = "# installed by `epb-prompt-git hooks install`";
+
+fn touch() -> String {
+    format!(": > \"$(git rev-parse --git-dir)/{}\"", cache::STAMP)
+}
+
+fn script() -> String {
+    format!("#!/bin/sh\n{MARKER}\n{}\n", touch())
+}
+
+/// Writes hooks touching [`cache::STAMP`] into the hooks dir of the repo at `path`, existing hooks
+/// not written by us are skipped and reported.
+pub fn install(out: &mut impl Write, path: &Path) -> io::Result<()> {
+    let dir = util::git(path)
+        .args(["rev-parse", "--path-format=absolute", "--git-path", "hooks"])
+        .output()?;
+
+    if !dir.status.success() {
+        return Err(io::Error::other(format!("not a repo: {}", path.display())));
+    }
+
+    let dir = Path::new(String::from_utf8_lossy(&dir.stdout).trim_end()).to_owned();
+    fs::create_dir_all(&dir)?;
+
+    let script = script();
+    for hook in HOOKS {
+        let hook_path = dir.join(hook);
+
+        match fs::read_to_string(&hook_path) {
+            Ok(existing) if !existing.contains(MARKER) => {
+                writeln!(
+                    out,
+                    "skipped:   {} exists, add `{}` to it yourself",
+                    hook_path.display(),
+                    touch()
+                )?;
+                continue;
+            }
+            Ok(existing) if existing == script => {
+                writeln!(out, "unchanged: {}", hook_path.display())?;
+                continue;
+            }
+            Ok(_) => {}
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+
+        fs::write(&hook_path, &script)?;
+        make_executable(&hook_path)?;
+        writeln!(out, "installed: {}", hook_path.display())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+}
+
+// git for windows runs hooks through its own sh regardless of permissions
+#[cfg(not(unix))]
+fn make_executable(_: &Path) -> io::Result<()> {
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use std::path::Path;
+
+use crate::cli::Shell;
+
+// `'...'` with embedded quotes closed, escaped and reopened, the same in all three shells
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+// outside of repos there is nothing to show, `git rev-parse` is cheaper than a full status, and
+// the kill switch in the environment spares even that, see `crate::disabled`
+const ZSH: &str = r#"# eval "$(epb-prompt-git init zsh)" in ~/.zshrc
+_epb_prompt_git() {
+    _epb_prompt_git_out=
+    [[ ${EPB_PROMPT_DISABLE:-0} == 0 ]] || return
+    command git rev-parse --git-dir >/dev/null 2>&1 || return
+    _epb_prompt_git_out="$({exe} --shell=zsh 2>/dev/null)" || _epb_prompt_git_out=
+}
+
+if (( ! ${precmd_functions[(I)_epb_prompt_git]} )); then
+    precmd_functions+=(_epb_prompt_git)
+    # the output is escaped for the prompt, but only expanded as a parameter it is not run
+    setopt prompt_subst
+    PROMPT='${_epb_prompt_git_out:+$_epb_prompt_git_out }'"$PROMPT"
+fi
+"#;
+
+const BASH: &str = r#"# eval "$(epb-prompt-git init bash)" in ~/.bashrc
+_epb_prompt_git_ps1=${_epb_prompt_git_ps1-$PS1}
+_epb_prompt_git() {
+    local status=$? out=
+    if [[ ${EPB_PROMPT_DISABLE:-0} == 0 ]] &&
+        command git rev-parse --git-dir >/dev/null 2>&1; then
+        out="$({exe} --shell=bash 2>/dev/null)" || out=
+    fi
+    PS1="${out:+$out }$_epb_prompt_git_ps1"
+    return $status
+}
+
+if [[ $PROMPT_COMMAND != *_epb_prompt_git* ]]; then
+    PROMPT_COMMAND="_epb_prompt_git${PROMPT_COMMAND:+;$PROMPT_COMMAND}"
+fi
+"#;
+
+const FISH: &str = r#"# epb-prompt-git init fish | source in ~/.config/fish/config.fish
+function __epb_prompt_git_status
+    return $argv[1]
+end
+
+if not functions -q __epb_prompt_git_prompt
+    if functions -q fish_prompt
+        functions -c fish_prompt __epb_prompt_git_prompt
+    else
+        function __epb_prompt_git_prompt
+        end
+    end
+
+    function fish_prompt
+        set -l status_ $status
+        if contains -- "$EPB_PROMPT_DISABLE" '' 0
+            and command git rev-parse --git-dir >/dev/null 2>&1
+            set -l out ({exe} --shell=fish 2>/dev/null)
+            and printf '%s ' $out
+        end
+        # the wrapped prompt shows the status of the last command, not ours
+        __epb_prompt_git_status $status_
+        __epb_prompt_git_prompt
+    end
+end
+"#;
+
+/// Shell code sourcing the prompt of the binary at `exe` into the prompt of `shell`, printed by
+/// `init <shell>`.
+///
+/// The path is baked in so prompts neither depend on nor search `PATH`, the existing prompt is
+/// kept and prefixed.
+pub fn script(shell: Shell, exe: &Path) -> String {
+    let template = match shell {
+        Shell::Zsh => ZSH,
+        Shell::Bash => BASH,
+        Shell::Fish => FISH,
+    };
+
+    template.replace("{exe}", &quote(&exe.to_string_lossy()))
+}
@@ -0,0 +1,533 @@
+use std::{collections::BTreeMap, env};
+#[cfg(feature = "cli")]
+use std::{error::Error, path::Path, time::Instant};
+
+use repo::{Change, Changes, Conflicts, Submodules};
+
+pub mod adaptive;
+mod cache;
+pub mod check;
+pub mod cli;
+pub mod config;
+pub mod dirs;
+#[cfg(feature = "cli")]
+pub mod doctor;
+pub mod forge;
+pub mod gallery;
+pub mod history;
+#[cfg(feature = "cli")]
+pub mod hooks;
+pub mod init;
+#[cfg(feature = "cli")]
+pub mod native;
+pub mod output;
+#[cfg(feature = "cli")]
+pub mod progressive;
+pub mod repo;
+#[cfg(feature = "cli")]
+pub mod segment;
+pub mod ticket;
+#[cfg(feature = "cli")]
+pub mod tui;
+pub mod util;
+#[cfg(feature = "cli")]
+pub mod workspace;
+
+/// The snapshot `git status --porcelain=v2 --branch --show-stash` describes with `porcelain`,
+/// without anything else measured, e.g. for rendering output captured elsewhere.
+pub fn parse_status(porcelain: &str, config: &config::Config) -> repo::RepoSnapshot {
+    let mut commit = None;
+    let (mut local, mut remote) = (None, None);
+    let (mut ahead, mut behind, mut stash, mut ignored) = (0, 0, 0, 0);
+    let mut has_ab = false;
+    let (mut working_tree, mut index) = (Changes::new(), Changes::new());
+    let mut submodules = Submodules::new();
+    let mut conflicts = Conflicts::new();
+    let untracked_dirs = config.get_bool("untracked.dirs").unwrap_or(false);
+    let mut headers = BTreeMap::new();
+    let mut warnings = vec![];
+
+    for line in porcelain.lines().filter(|s| !s.is_empty()) {
+        // # branch.oid <commit> | (initial)        Current commit.
+        // # branch.head <branch> | (detached)      Current branch.
+        // # branch.upstream <upstream>/<branch>    If upstream is set.
+        // # branch.ab +<ahead> -<behind>           If upstream is set and the commit is present.
+        //                                          Missing if the upstream branch is gone.
+        if let Some(rest) = line.strip_prefix("# branch.") {
+            if let Some(oid) = rest.strip_prefix("oid ") {
+                commit = (oid != "(initial)").then_some(oid);
+                continue;
+            }
+
+            if let Some(name) = rest.strip_prefix("head ") {
+                local = (name != "(detached)").then_some(name);
+                continue;
+            }
+
+            if let Some(upstream) = rest.strip_prefix("upstream ") {
+                remote = Some(upstream);
+                continue;
+            }
+
+            let counts = rest
+                .strip_prefix("ab +")
+                .and_then(|rest| rest.split_once(" -"))
+                .and_then(|(a, b)| Some((a.parse().ok()?, b.parse().ok()?)));
+
+            if let Some(counts) = counts {
+                (ahead, behind) = counts;
+                has_ab = true;
+                continue;
+            }
+        }
+
+        // # stash <N>  stashed
+        if let Some(Ok(count)) = line
+            .strip_prefix("# stash ")
+            .map(|rest| rest.trim().parse())
+        {
+            stash = count;
+            continue;
+        }
+
+        // # <key> <value>  anything a later git adds or a known header in an unexpected format
+        if let Some(header) = line.strip_prefix("# ") {
+            let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+            headers.insert(key.to_owned(), value.to_owned());
+            continue;
+        }
+
+        // ? <path>     untracked, directories end in `/`
+        if let Some(path) = line.strip_prefix("? ") {
+            if untracked_dirs && path.ends_with('/') {
+                working_tree[Change::UntrackedDir] += 1;
+            } else {
+                working_tree[Change::Untracked] += 1;
+            }
+            continue;
+        }
+
+        // ! <path>     ignored
+        if line.starts_with("! ") {
+            ignored += 1;
+            continue;
+        }
+
+        // .x   not updated
+        // Mx   updated in index
+        // Tx   type changed in index
+        // Ax   added to index
+        // Dx   deleted from index
+        // x.   index and work tree matches
+        // xM   work tree changed since index
+        // xT   type changed in work tree since index
+        // xD   deleted in work tree
+
+        // S<c><m><u>    submodule state, see `Submodules::add`
+        // the submodule entry itself is counted as a change regardless of its kind
+        if let Some((x, y, state)) = util::parse_submodule_line(line, "1 ")
+            .or_else(|| util::parse_submodule_line(line, "2 "))
+        {
+            if x != '.' {
+                index[Change::Sub] += 1;
+            }
+
+            if y != '.' {
+                working_tree[Change::Sub] += 1;
+            }
+
+            submodules.add(state);
+            continue;
+        }
+
+        // changes
+        if let Some((x, y)) = util::parse_xy_line(line, "1 ") {
+            match x {
+                '.' => {}
+                'A' => index[Change::Add] += 1,
+                'M' => index[Change::Mod] += 1,
+                'D' => index[Change::Del] += 1,
+                'T' => index[Change::Typ] += 1,
+                x => warnings.push(format!("unknown index change `{x}` in `{line}`")),
+            }
+
+            match y {
+                '.' => {}
+                'A' => working_tree[Change::Add] += 1,
+                'M' => working_tree[Change::Mod] += 1,
+                'D' => working_tree[Change::Del] += 1,
+                'T' => working_tree[Change::Typ] += 1,
+                y => warnings.push(format!("unknown work tree change `{y}` in `{line}`")),
+            }
+
+            continue;
+        }
+
+        // Cx   copied in index
+        // Rx   renamed in index
+        // xR   renamed in work tree
+        // xC   copied in work tree
+        if let Some((x, y)) = util::parse_xy_line(line, "2 ") {
+            match x {
+                '.' => {}
+                'R' => index[Change::Ren] += 1,
+                'C' => index[Change::Cpy] += 1,
+                'M' => index[Change::Mod] += 1,
+                x => warnings.push(format!("unknown index change `{x}` in `{line}`")),
+            }
+
+            match y {
+                '.' => {}
+                'R' => working_tree[Change::Ren] += 1,
+                'C' => working_tree[Change::Cpy] += 1,
+                'M' => working_tree[Change::Mod] += 1,
+                y => warnings.push(format!("unknown work tree change `{y}` in `{line}`")),
+            }
+
+            continue;
+        }
+
+        // DD   both deleted
+        // AU   added by us
+        // UD   deleted by them
+        // UA   added by them
+        // DU   deleted by us
+        // AA   both added
+        // UU   both modified
+        if let Some(xy) = util::parse_xy_line(line, "u ") {
+            conflicts.add(xy);
+            continue;
+        }
+
+        warnings.push(format!("unknown status line `{line}`"));
+    }
+
+    repo::RepoSnapshot {
+        measured_at: util::unix_now(),
+        commit: commit.map(str::to_owned),
+        branch: local.map(str::to_owned),
+        upstream: remote.map(str::to_owned),
+        upstream_gone: remote.is_some() && !has_ab,
+        protected: local.is_some_and(|local| check::is_protected(local, config)),
+        ahead,
+        behind,
+        working_tree,
+        index,
+        submodules,
+        conflicts,
+        stash,
+        ignored: config
+            .get_bool("status.ignored")
+            .unwrap_or(false)
+            .then_some(ignored),
+        headers,
+        warnings,
+        ..Default::default()
+    }
+}
+
+/// Why the prompt is switched off, `EPB_PROMPT_DISABLE` set to anything but the empty string or `0`
+/// or [`dirs::disable_file`] existing, `None` if it is not.
+///
+/// Single repos are switched off by `disable`, e.g. `git config prompt.disable true`, which is
+/// only known once the config is read.
+///
+/// Checked before anything else, neither the config is read nor git is run then.
+pub fn disabled() -> Option<String> {
+    if env::var_os("EPB_PROMPT_DISABLE").is_some_and(|value| !value.is_empty() && value != "0") {
+        return Some("EPB_PROMPT_DISABLE is set".to_owned());
+    }
+
+    dirs::disable_file()
+        .filter(|file| file.exists())
+        .map(|file| format!("{} exists", file.display()))
+}
+
+/// Runs the status engine over the repo at `path`.
+#[cfg(feature = "cli")]
+pub fn get_snapshot(
+    path: &Path,
+    config: &config::Config,
+) -> Result<repo::RepoSnapshot, Box<dyn Error>> {
+    // skip the untracked scan in repos where status was too slow before
+    let reduced = adaptive::is_reduced(path, config);
+    let show_ignored = !reduced && config.get_bool("status.ignored").unwrap_or(false);
+    let untracked = !reduced && config.get_bool("status.untracked").unwrap_or(true);
+
+    // use https://git-scm.com/docs/git-status
+    let start = Instant::now();
+    let output = util::git(path)
+        .args([
+            "status",
+            "--porcelain=v2",
+            "--column",
+            "--branch",
+            "--show-stash",
+        ])
+        .args((!untracked).then_some("--untracked-files=no"))
+        .args(show_ignored.then_some("--ignored=matching"))
+        .output()?;
+
+    // status needs a work tree, e.g. while looking at hooks there is none
+    if !output.status.success() && util::is_inside_git_dir(path)? {
+        return Ok(repo::RepoSnapshot {
+            measured_at: util::unix_now(),
+            inside_git_dir: true,
+            ..Default::default()
+        });
+    }
+
+    if !reduced {
+        adaptive::record(path, start.elapsed());
+    }
+
+    let mut snapshot = parse_status(&String::from_utf8_lossy(&output.stdout), config);
+    snapshot.reduced = reduced;
+    if reduced {
+        snapshot.ignored = None;
+        snapshot.warnings.push(
+            "untracked files, the diffstat and describe were skipped as status was slow before"
+                .to_owned(),
+        );
+    }
+
+    let (commit, local) = (snapshot.commit.clone(), snapshot.branch.clone());
+    let (commit, local) = (commit.as_deref(), local.as_deref());
+    let (stash, conflicts) = (snapshot.stash, snapshot.conflicts);
+
+    if config.get_bool("status.hidden").unwrap_or(false) {
+        snapshot.hidden = util::try_get_hidden_count(path)?;
+    }
+
+    // skipped like the untracked scan, diffs scale with the size of the changes
+    if !reduced && config.get_bool("diff.stat").unwrap_or(false) {
+        if snapshot.working_tree.any() {
+            snapshot.unstaged_lines = util::try_get_diffstat(path, false)?;
+        }
+
+        if snapshot.index.any() {
+            snapshot.staged_lines = util::try_get_diffstat(path, true)?;
+        }
+    }
+
+    if stash != 0 && config.get_bool("stash.subject").unwrap_or(false) {
+        snapshot.stash_subject = util::try_get_stash_subject(path)?;
+    }
+
+    let Some(commit) = commit else {
+        return Ok(snapshot);
+    };
+
+    // in linked worktrees `.git` is a file pointing at the actual git dir, which differs from the
+    // common dir shared by all worktrees
+    let (git_dir, common_dir) = util::try_get_git_dirs(path)?.ok_or("could not resolve git dir")?;
+    snapshot.worktree = git_dir != common_dir;
+
+    if let Some(stale) = config.get_int("fetch.stale").filter(|&secs| secs > 0) {
+        snapshot.fetched = util::try_get_last_fetch(&git_dir, &common_dir)
+            .map(|time| util::unix_now().saturating_sub(time))
+            .filter(|&ago| ago >= stale as u64);
+    }
+
+    if config.get_bool("head.subject").unwrap_or(false) {
+        snapshot.subject = util::try_get_subject(path, commit)?;
+    }
+
+    if let Some(window) = config.get_int("head.switched").filter(|&secs| secs > 0) {
+        snapshot.switched = util::try_get_last_checkout(&git_dir)?
+            .map(|time| util::unix_now().saturating_sub(time))
+            .filter(|&ago| ago < window as u64);
+    }
+
+    if config.get_bool("head.signature").unwrap_or(false) {
+        snapshot.signature = util::try_get_signature(path, commit)?;
+    }
+
+    // only detached heads show their hash
+    if local.is_none() && config.get_bool("hash.abbrev").unwrap_or(false) {
+        snapshot.abbrev = util::try_get_abbrev(path, commit)?.map(|hash| hash.len());
+    }
+
+    if config.get_bool("head.age").unwrap_or(false) {
+        snapshot.head_age = util::try_get_head_age(path, commit)?;
+    }
+
+    if config.get_bool("links.enabled").unwrap_or(false) {
+        // detached heads have no upstream, their commit is most likely on `origin`
+        let remote = match snapshot
+            .upstream
+            .as_deref()
+            .map(|name| name.split_once('/'))
+        {
+            Some(Some((remote, _))) => Some(remote),
+            Some(None) => None,
+            None => Some("origin"),
+        };
+
+        if let Some(remote) = remote {
+            snapshot.forge = util::try_get_remote_url(path, remote)?
+                .as_deref()
+                .and_then(forge::Forge::from_url);
+        }
+    }
+
+    // a merge or rebase may also be paused without any conflicts, e.g. after `merge --no-commit` or
+    // at an `edit` step of an interactive rebase
+    let merge_head = util::try_get_file_content(git_dir.join("MERGE_HEAD"))?;
+    let pick_head = util::try_get_file_content(git_dir.join("CHERRY_PICK_HEAD"))?;
+    let revert_head = util::try_get_file_content(git_dir.join("REVERT_HEAD"))?;
+    let rebase_head = util::try_get_file_content(git_dir.join("REBASE_HEAD"))?;
+    let rebase_state = util::try_get_rebase_state(&git_dir)?;
+
+    if conflicts.any()
+        || merge_head.is_some()
+        || pick_head.is_some()
+        || revert_head.is_some()
+        || rebase_head.is_some()
+        || rebase_state.is_some()
+    {
+        let output = util::git(path).arg("show-ref").output()?;
+
+        let lines = String::from_utf8_lossy(&output.stdout);
+
+        // a detached head is shown as its hash, not as a branch that happens to point at it
+        let head = match local {
+            Some(local) => repo::ConflictRef::branch(local.to_owned()),
+            None => repo::ConflictRef::commit(commit.to_owned()),
+        };
+
+        let (kind, source, target) = if let Some(merge_head) = merge_head {
+            (repo::ConflictKind::Merge, head, merge_head)
+        } else if rebase_head.is_some() || rebase_state.is_some() {
+            // the rebase state dir knows which branch is being rebased and onto what, prefer that
+            // over the commit currently being picked
+            let (head_name, onto) = rebase_state.unwrap_or_default();
+            (
+                repo::ConflictKind::Rebase,
+                resolve_head(&lines, &onto.unwrap_or_else(|| commit.to_owned())),
+                head_name
+                    .or(rebase_head)
+                    .unwrap_or_else(|| commit.to_owned()),
+            )
+        } else if let Some(pick_head) = pick_head {
+            (repo::ConflictKind::CherryPick, head, pick_head)
+        } else if let Some(revert_head) = revert_head {
+            (repo::ConflictKind::Revert, head, revert_head)
+        } else {
+            // e.g. `stash pop` or `checkout -m`, there is nothing but HEAD
+            (repo::ConflictKind::Other, head, commit.to_owned())
+        };
+
+        // only use `refs/heads`
+        // this may need to be recursive
+        fn resolve_head(refs: &str, reference: &str) -> repo::ConflictRef {
+            if let Some(branch) = reference.strip_prefix("refs/heads/") {
+                return repo::ConflictRef::branch(branch.to_owned());
+            }
+
+            let resolved = refs
+                .lines()
+                .map(|line| line.split_once(' ').expect("<id> <ref>"))
+                .find_map(|(id, resolved)| {
+                    (id == reference)
+                        .then(|| resolved.strip_prefix("refs/heads/"))
+                        .flatten()
+                });
+
+            match resolved {
+                Some(branch) => repo::ConflictRef::branch(branch.to_owned()),
+                None => repo::ConflictRef::commit(reference.to_owned()),
+            }
+        }
+
+        snapshot.operation = Some(repo::Operation {
+            kind,
+            source,
+            target: resolve_head(&lines, &target),
+        });
+
+        return Ok(snapshot);
+    }
+
+    if local.is_none() {
+        snapshot.tag = util::try_get_tag(path)?;
+
+        if snapshot.tag.is_none()
+            && !reduced
+            && config.get_bool("detached.describe").unwrap_or(false)
+        {
+            snapshot.describe = util::try_get_describe(path, commit)?;
+        }
+
+        return Ok(snapshot);
+    }
+
+    snapshot.ticket = local.and_then(|local| ticket::extract(local, config));
+
+    if let (Some(local), Some(ci)) = (local, segment::Custom::from_config("ci", config)) {
+        snapshot.ci = ci.output(path, &git_dir, local, &mut snapshot.warnings);
+    }
+
+    if let (Some(local), Some(pr)) = (local, segment::Custom::from_config("pr", config)) {
+        snapshot.pr = pr.output(path, &git_dir, local, &mut snapshot.warnings);
+    }
+
+    if config.get_bool("branch.tag").unwrap_or(false) {
+        snapshot.tag = util::try_get_tag(path)?;
+    }
+
+    if config.get_bool("branch.push").unwrap_or(false) {
+        snapshot.push = util::try_get_comparison(path, "@{push}")?
+            .filter(|(name, _, _)| snapshot.upstream.as_ref() != Some(name))
+            .map(|(name, ahead, behind)| repo::Comparison {
+                name,
+                ahead,
+                behind,
+            });
+    }
+
+    if snapshot.upstream.is_none() && config.get_bool("branch.fallback").unwrap_or(false) {
+        let base = config
+            .get_str("branch.base")
+            .unwrap_or("refs/remotes/origin/HEAD");
+        snapshot.fallback = util::try_get_comparison(path, base)?
+            .filter(|(name, _, _)| local != Some(name.as_str()))
+            .map(|(name, ahead, behind)| repo::Comparison {
+                name,
+                ahead,
+                behind,
+            });
+    }
+
+    if config.get_bool("stack.enabled").unwrap_or(false) {
+        let pattern = config.get_str("stack.pattern").unwrap_or("refs/heads");
+        snapshot.stacked = util::try_get_stacked(path, commit, pattern)?.filter(|&n| n != 0);
+    }
+
+    let show_age = config.get_bool("branch.age").unwrap_or(false);
+    let show_commits = config.get_bool("branch.commits").unwrap_or(false);
+
+    let base = if show_age || show_commits {
+        util::try_get_base(path, config.get_str("branch.base"))?
+    } else {
+        None
+    };
+
+    if let Some(base) = &base {
+        if show_age {
+            snapshot.age = util::try_get_branch_age(path, commit, base)?;
+        }
+
+        if show_commits {
+            snapshot.commits = util::try_get_branch_commits(path, commit, base)?;
+        }
+    }
+
+    Ok(snapshot)
+}
+
+/// Runs the status engine over the repo at `path`, see [`get_snapshot`].
+#[cfg(feature = "cli")]
+pub fn get_prompt(path: &Path, config: &config::Config) -> Result<repo::Prompt, Box<dyn Error>> {
+    get_snapshot(path, config).map(|snapshot| snapshot.prompt())
+}
@@ -1,314 +1,471 @@
 use std::{
     env,
-    error::Error,
-    path::{Path, PathBuf},
-    process::{self, Command},
+    io::{self, IsTerminal},
+    process,
 };
 
-use repo::{Change, Changes};
-
-mod repo;
-mod util;
-
-fn get_prompt(path: &Path) -> Result<repo::Prompt, Box<dyn Error>> {
-    // use https://git-scm.com/docs/git-status
-    let output = Command::new("git")
-        .current_dir(path)
-        .args([
-            "status",
-            "--porcelain=v2",
-            "--column",
-            "--branch",
-            "--show-stash",
-        ])
-        .output()?;
-
-    let lines = String::from_utf8_lossy(&output.stdout);
-
-    let mut commit = None;
-    let (mut local, mut remote) = (None, None);
-    let (mut ahead, mut behind, mut conflicts, mut stash, mut _ignored) = (0, 0, 0, 0, 0);
-    let (mut working_tree, mut index) = (Changes::new(), Changes::new());
-
-    for line in lines.lines().filter(|s| !s.is_empty()) {
-        // # branch.oid <commit> | (initial)        Current commit.
-        // # branch.head <branch> | (detached)      Current branch.
-        // # branch.upstream <upstream>/<branch>    If upstream is set.
-        // # branch.ab +<ahead> -<behind>           If upstream is set and the commit is present.
-        if let Some(rest) = line.strip_prefix("# branch.") {
-            if let Some(oid) = rest.strip_prefix("oid ") {
-                commit = (oid != "(initial)").then_some(oid);
-                continue;
-            }
+use epb_prompt_git::{
+    check, cli, config, dirs, disabled, doctor, gallery, get_snapshot, history, hooks, init,
+    native, output, progressive, repo, tui, util, workspace,
+};
 
-            if let Some(name) = rest.strip_prefix("head ") {
-                local = (name != "(detached)").then_some(name);
-                continue;
-            }
+fn main() {
+    let args = cli::Args::parse().unwrap_or_else(|err| {
+        eprintln!("epb-prompt-git: {err}");
+        process::exit(2)
+    });
 
-            if let Some(upstream) = rest.strip_prefix("upstream ") {
-                remote = Some(upstream);
-                continue;
-            }
+    // the kill switch, nothing at all is printed
+    if args.command == cli::Command::Prompt && disabled().is_some() {
+        return;
+    }
 
-            if let Some(rest) = rest.strip_prefix("ab +") {
-                let (aheadstr, behindstr) = rest.split_once(" -").unwrap();
+    // an open directory stands in for the pwd, see `util::dir_fd_path`
+    let pwd = match args.dir_fd {
+        Some(fd) => util::dir_fd_path(fd).unwrap_or_else(|err| {
+            eprintln!("epb-prompt-git: --dir-fd: {err}");
+            process::exit(2)
+        }),
+        None => env::current_dir().expect("could not acquire pwd"),
+    };
+    let debug = args.debug;
 
-                ahead = aheadstr.parse().expect("valid count");
-                behind = behindstr.parse().expect("valid count");
-                continue;
-            }
-        }
+    if debug {
+        eprintln!("config:  {:?}", dirs::config_dir());
+        eprintln!("cache:   {:?}", dirs::cache_dir());
+        eprintln!("state:   {:?}", dirs::state_dir());
+        eprintln!("runtime: {:?}", dirs::runtime_dir());
+    }
 
-        // # stash <N>  stashed
-        if let Some(rest) = line.strip_prefix("# stash ") {
-            stash = rest.trim().parse()?;
-            continue;
+    let mut warnings = vec![];
+    let mut config = config::Config::load(&mut warnings).unwrap_or_else(|err| {
+        warnings.push(format!("config: {err}"));
+        config::Config::default()
+    });
+
+    // this will return `pwd` if `arg_path` was `None`
+    let path = util::path_rel_to_abs(&pwd, args.path.as_deref());
+
+    // per repo settings, between the config file and the flags
+    let reads_repo = !matches!(
+        args.command,
+        cli::Command::Init
+            | cli::Command::Gallery
+            | cli::Command::History
+            | cli::Command::Workspace
+    );
+    if let Some(git_dir) = util::find_git_dir(&path).filter(|_| reads_repo) {
+        match config::Config::load_from(&git_dir.join(config::REPO_FILE), &mut warnings) {
+            Ok(repo) => config.merge(repo),
+            Err(err) => warnings.push(format!("config: {err}")),
         }
+    }
 
-        // ? <path>     untracked
-        if line.starts_with("? ") {
-            working_tree[Change::Add] += 1;
-            continue;
+    if reads_repo && !args.sandbox {
+        match util::try_get_prompt_config(&path) {
+            Ok(Some(listing)) => config.apply_git(&listing, &mut warnings),
+            Ok(None) => {}
+            Err(err) => warnings.push(format!("git config: {err}")),
         }
+    }
 
-        // ! <path>     ignored
-        if line.starts_with("! ") {
-            _ignored += 1;
-            continue;
+    if args.verbose {
+        config.set("head.subject", config::Value::Bool(true));
+        config.set("stash.subject", config::Value::Bool(true));
+    }
+
+    if args.show_ignored {
+        config.set("status.ignored", config::Value::Bool(true));
+    }
+
+    for (key, value) in &args.set {
+        if let Err(err) = config.set_str(key, value) {
+            eprintln!("epb-prompt-git: --set: {err}");
+            process::exit(2)
         }
+    }
 
-        // .x   not updated
-        // Mx   updated in index
-        // Tx   type changed in index
-        // Ax   added to index
-        // Dx   deleted from index
-        // x.   index and work tree matches
-        // xM   work tree changed since index
-        // xT   type changed in work tree since index
-        // xD   deleted in work tree
-
-        // changes
-        if let Some((x, y)) = util::parse_xy_line(line, "1 ") {
-            match x {
-                '.' => {}
-                'A' => index[Change::Add] += 1,
-                'M' => index[Change::Mod] += 1,
-                'D' => index[Change::Del] += 1,
-                'T' => index[Change::Typ] += 1,
-                x => eprintln!("idx: {x}"),
-            }
+    // a bad template on the command line is an error, one in the config falls back to the default
+    let template = match &args.format {
+        Some(format) => Some(repo::Template::parse(format).unwrap_or_else(|err| {
+            eprintln!("epb-prompt-git: --format: {err}");
+            process::exit(2)
+        })),
+        None => config
+            .get_str("format")
+            .and_then(|format| match repo::Template::parse(format) {
+                Ok(template) => Some(template),
+                Err(err) => {
+                    warnings.push(format!("config: format: {err}"));
+                    None
+                }
+            }),
+    };
 
-            match y {
-                '.' => {}
-                'A' => working_tree[Change::Add] += 1,
-                'M' => working_tree[Change::Mod] += 1,
-                'D' => working_tree[Change::Del] += 1,
-                'T' => working_tree[Change::Typ] += 1,
-                x => eprintln!("idx: {x}"),
-            }
+    // `format` says it all, `order` merely rearranges the built-in segments
+    let template = template.or_else(|| {
+        let names = config.get_list("order")?.iter().map(|value| match value {
+            config::Value::Str(name) => name.as_str(),
+            _ => "",
+        });
+        repo::Template::from_order(names)
+            .map_err(|err| warnings.push(format!("config: order: {err}")))
+            .ok()
+    });
+
+    // an unknown theme in the config falls back to the default one
+    let theme = args.theme.unwrap_or_else(|| match config.get_str("theme") {
+        Some(name) => repo::Theme::from_name(name).unwrap_or_else(|| {
+            warnings.push(format!("config: theme: unknown theme `{name}`"));
+            repo::Theme::Default
+        }),
+        None => repo::Theme::Default,
+    });
+
+    // only `auto` looks at the terminal, there is no telling if it does not say
+    let background = match config.get_str("background") {
+        Some("auto") => env::var("COLORFGBG")
+            .ok()
+            .and_then(|value| repo::Background::from_colorfgbg(&value))
+            .unwrap_or_default(),
+        Some(name) => repo::Background::from_name(name).unwrap_or_else(|| {
+            warnings.push(format!("config: background: unknown background `{name}`"));
+            repo::Background::Dark
+        }),
+        None => repo::Background::Dark,
+    };
 
+    let mut overrides = repo::Overrides::default();
+    for (key, glyph) in [
+        ("symbol.ahead", repo::Glyph::Ahead),
+        ("symbol.behind", repo::Glyph::Behind),
+        ("symbol.stash", repo::Glyph::Stash),
+        ("symbol.conflict", repo::Glyph::Conflict),
+        ("symbol.merge", repo::Glyph::Merge),
+        ("symbol.rebase", repo::Glyph::Rebase),
+    ] {
+        if let Some(text) = config.get_str(key) {
+            overrides.set_glyph(glyph, text);
+        }
+    }
+
+    // an unknown role or color is left as the theme has it
+    for (key, value) in config.iter() {
+        let Some(name) = key.strip_prefix("color.") else {
             continue;
+        };
+        let Some(role) = repo::Role::from_name(name) else {
+            warnings.push(format!("config: {key}: unknown role `{name}`"));
+            continue;
+        };
+        let config::Value::Str(value) = value else {
+            let kind = config::Kind::Str;
+            warnings.push(format!("config: `{key}` must be {kind}, got {value}"));
+            continue;
+        };
+
+        match repo::Style::parse(value) {
+            Ok(style) => overrides.set_style(role, style),
+            Err(err) => warnings.push(format!("config: {key}: {err}")),
         }
+    }
 
-        // Cx   copied in index
-        // Rx   renamed in index
-        // xR   renamed in work tree
-        // xC   copied in work tree
-        if let Some((x, y)) = util::parse_xy_line(line, "2 ") {
-            match x {
-                '.' => {}
-                'R' => index[Change::Ren] += 1,
-                'C' => {}
-                'M' => index[Change::Mod] += 1,
-                x => eprintln!("idx: {x}"),
-            }
+    if let Some(text) = config.get_str("separator") {
+        overrides.set_separator(text);
+    }
 
-            match y {
-                '.' => {}
-                'R' => working_tree[Change::Ren] += 1,
-                'C' => {}
-                'M' => working_tree[Change::Mod] += 1,
-                x => eprintln!("idx: {x}"),
-            }
+    let thresholds = config
+        .iter()
+        .filter_map(|(key, _)| key.strip_prefix("count."));
+    for name in thresholds.filter(|&name| name != "cap") {
+        if !repo::Change::ALL.iter().any(|change| change.name() == name) {
+            warnings.push(format!("config: count.{name}: unknown change `{name}`"));
+        }
+    }
 
-            continue;
+    if let Some(cap) = config.get_int("count.cap").filter(|&cap| cap > 0) {
+        overrides.set_cap(cap as usize);
+    }
+
+    for change in repo::Change::ALL {
+        if let Some(threshold) = config.get_int(&format!("count.{}", change.name())) {
+            overrides.set_threshold(change, threshold.max(0) as usize);
         }
 
-        // DD   both deleted
-        // AU   added by us
-        // UD   deleted by them
-        // UA   added by them
-        // DU   deleted by us
-        // AA   both added
-        // UU   both modified
-        if let Some(_) = util::parse_xy_line(line, "u ") {
-            conflicts += 1;
-            continue;
+        if let Some(text) = config.get_str(&format!("symbol.{}", change.name())) {
+            overrides.set_glyph(repo::Glyph::Change(change), text);
         }
     }
 
-    // eprintln!("commit:      {:?}", commit);
-    // eprintln!("local:       {:?}", local);
-    // eprintln!("remote:      {:?}", remote);
-    // eprintln!("ab:          {:?}", (ahead, behind));
-    // eprintln!("conflict:    {:?}", conflicts);
-    // eprintln!("stash:       {:?}", stash);
-    // eprintln!("ignore:      {:?}", ignored);
-    // eprintln!("wt:          {:?}", working_tree);
-    // eprintln!("idx:         {:?}", index);
-
-    let commit = if let Some(commit) = commit {
-        commit
-    } else {
-        return Ok(repo::Prompt::headless(working_tree, index, stash));
+    for warning in &warnings {
+        eprintln!("epb-prompt-git: {warning}");
+    }
+
+    if debug {
+        for (key, value) in config.iter() {
+            eprintln!("{key} = {value}");
+        }
+    }
+
+    // the kill switch of a single repo, e.g. `git config prompt.disable true`
+    if args.command == cli::Command::Prompt && config.get_bool("disable").unwrap_or(false) {
+        return;
+    }
+
+    // see https://no-color.org and https://bixense.com/clicolors, `0` does not force color
+    let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let force_color =
+        env::var_os("CLICOLOR_FORCE").is_some_and(|value| !value.is_empty() && value != "0");
+    let color = match args.color {
+        Some(cli::ColorChoice::Always) => true,
+        Some(cli::ColorChoice::Never) => false,
+        Some(cli::ColorChoice::Auto) => io::stdout().is_terminal(),
+        None => force_color || !no_color,
     };
 
-    let local = if let Some(local) = local {
-        local
-    } else {
-        // if conflicts are non zero then this may be a detached rebase head
-        if conflicts == 0 {
-            let mut commit = commit;
-            let output = Command::new("git")
-                .current_dir(path)
-                .arg("show-ref")
-                .output()?;
-
-            let lines = String::from_utf8_lossy(&output.stdout);
-
-            // see notes below
-            let mut is_commit_resolved = false;
-            for (id, resolved) in lines
-                .lines()
-                .map(|line| line.split_once(' ').expect("<id> <ref>"))
-            {
-                if id == commit {
-                    commit = resolved;
-                    is_commit_resolved = true;
-                }
+    let options = repo::RenderOptions {
+        color,
+        // the sandbox can't tell the upstream, see `native::get_snapshot`
+        sparse: args.sandbox,
+        detailed_conflicts: config.get_bool("conflicts.detailed").unwrap_or(false),
+        full_upstream: config.get_bool("upstream.full").unwrap_or(false),
+        compact: config.get_bool("upstream.compact").unwrap_or(false),
+        markup: match args.output {
+            cli::Output::Tmux => repo::Markup::Tmux,
+            cli::Output::Html => repo::Markup::Html,
+            cli::Output::Zsh => repo::Markup::Zsh,
+            _ => repo::Markup::Ansi,
+        },
+        theme,
+        background,
+        ascii: args.ascii || config.get_bool("ascii").unwrap_or(false),
+        overrides: (!overrides.is_empty()).then_some(&overrides),
+        hints: config
+            .get_str("hint.style")
+            .and_then(repo::HintStyle::from_name),
+        hidden: args.hidden,
+        branch_len: config
+            .get_int("branch.length")
+            .filter(|&len| len > 0)
+            .map(|len| len as usize),
+        truncation: config
+            .get_str("branch.ellipsis")
+            .and_then(repo::Truncation::from_name)
+            .unwrap_or_default(),
+        hash_len: args.hash_len.or_else(|| {
+            let len = config.get_int("hash.length").filter(|&len| len > 0)?;
+            Some(len as usize)
+        }),
+        ..Default::default()
+    };
+
+    match args.command {
+        cli::Command::Prompt => {}
+        cli::Command::Doctor => {
+            if let Err(err) = doctor::report(&mut io::stdout().lock(), &path, &config) {
+                eprintln!("epb-prompt-git: {err}");
+                process::exit(1)
             }
 
-            fn resolve_tag(reference: &str, is_resolved: bool) -> repo::DetachedRef {
-                if is_resolved {
-                    repo::DetachedRef::tag(reference.trim_start_matches("refs/tags/").to_owned())
-                } else {
-                    repo::DetachedRef::commit(reference.to_owned())
-                }
+            return;
+        }
+        cli::Command::Tui => {
+            if let Err(err) = tui::run(&path, &config, &options) {
+                eprintln!("epb-prompt-git: {err}");
+                process::exit(1)
             }
 
-            return Ok(repo::Prompt::detached(
-                resolve_tag(commit, is_commit_resolved),
-                working_tree,
-                index,
-                stash,
-            ));
-        } else {
-            commit
+            return;
         }
-    };
+        cli::Command::Check => {
+            let risks = match get_snapshot(&path, &config) {
+                Ok(snapshot) => check::risks(&snapshot, &config),
+                Err(err) => vec![err.to_string()],
+            };
+
+            for risk in &risks {
+                eprintln!("epb-prompt-git: {risk}");
+            }
 
-    let remote_diverge = remote.map(|name| {
-        let (remote, branch) = name.split_once('/').unwrap();
-        (
-            repo::RemoteBranch::new(remote.to_owned(), branch.to_owned()),
-            (ahead + behind != 0).then(|| repo::Divergence::new(ahead, behind)),
-        )
-    });
+            if !risks.is_empty() {
+                process::exit(1)
+            }
 
-    if conflicts != 0 {
-        let output = Command::new("git")
-            .current_dir(path)
-            .arg("show-ref")
-            .output()?;
-
-        let lines = String::from_utf8_lossy(&output.stdout);
-
-        let ref_buffer; // not read so must not be always init
-        let (kind, mut source, mut target) = if let Some(merge_head) =
-            util::try_get_file_content(path.join(".git/MERGE_HEAD"))?
-        {
-            ref_buffer = merge_head;
-            (repo::ConflictKind::Merge, local, ref_buffer.as_str())
-        } else if let Some(rebase_head) = util::try_get_file_content(path.join(".git/REBASE_HEAD"))?
-        {
-            ref_buffer = rebase_head;
-            (repo::ConflictKind::Rebase, commit, ref_buffer.as_str())
-        } else {
-            todo!()
-        };
+            return;
+        }
+        cli::Command::Hooks => {
+            if let Err(err) = hooks::install(&mut io::stdout().lock(), &path) {
+                eprintln!("epb-prompt-git: {err}");
+                process::exit(1)
+            }
 
-        // only use if `refs/heads`?
-        // this may need to be recursive
-        let (mut is_source_resolved, mut is_target_resolved) = (false, false);
-        for (id, resolved) in lines
-            .lines()
-            .map(|line| line.split_once(' ').expect("<id> <ref>"))
-        {
-            if id == source {
-                source = resolved;
-                is_source_resolved = true;
-            } else if id == target {
-                target = resolved;
-                is_target_resolved = true;
+            return;
+        }
+        cli::Command::Init => {
+            let shell = args.shell.expect("required by cli");
+            match env::current_exe() {
+                Ok(exe) => print!("{}", init::script(shell, &exe)),
+                Err(err) => {
+                    eprintln!("epb-prompt-git: {err}");
+                    process::exit(1)
+                }
             }
+
+            return;
         }
+        cli::Command::Gallery => {
+            print!("{}", gallery::render(&options));
+            return;
+        }
+        cli::Command::History => {
+            if let Err(err) = history::summarize(&mut io::stdout().lock()) {
+                eprintln!("epb-prompt-git: {err}");
+                process::exit(1)
+            }
 
-        fn resolve_head(reference: &str, is_branch: bool) -> repo::ConflictRef {
-            if is_branch {
-                repo::ConflictRef::branch(reference.trim_start_matches("refs/heads/").to_owned())
+            return;
+        }
+        cli::Command::Workspace => {
+            let name = args.name.as_deref().expect("required by cli");
+            let result = if args.null {
+                let mut stdout = io::stdout().lock();
+                workspace::scan(name, &config, |path, snapshot| {
+                    let _ = output::record(&mut stdout, path, snapshot, args.output, &options);
+                })
             } else {
-                repo::ConflictRef::commit(reference.to_owned())
+                workspace::summarize(name, &config).map(|summary| println!("{summary:#}"))
+            };
+
+            if let Err(err) = result {
+                eprintln!("epb-prompt-git: {err}");
+                process::exit(1)
             }
-        }
 
-        return Ok(repo::Prompt::conflict(
-            kind,
-            resolve_head(&source, is_source_resolved),
-            resolve_head(&target, is_target_resolved),
-            working_tree,
-            index,
-            conflicts,
-            stash,
-        ));
+            return;
+        }
     }
 
-    if working_tree.any() || index.any() {
-        return Ok(repo::Prompt::working(
-            repo::Branch::new(local.to_owned(), remote_diverge),
-            working_tree,
-            index,
-            stash,
-        ));
+    // starship runs the command everywhere, outside of repos the module is meant to vanish
+    let starship = args.output == cli::Output::Starship;
+    if starship && util::find_git_dir(&path).is_none() {
+        process::exit(1)
     }
+    let snapshot = if args.sandbox {
+        native::get_snapshot(&path).map_err(|err| err.to_string())
+    } else if args.progressive {
+        progressive::with_placeholder(&path, || {
+            get_snapshot(&path, &config).map_err(|err| err.to_string())
+        })
+    } else {
+        get_snapshot(&path, &config).map_err(|err| err.to_string())
+    };
 
-    return Ok(repo::Prompt::clean(
-        repo::Branch::new(local.to_owned(), remote_diverge),
-        stash,
-    ));
-}
+    // everything printed ends up in the shell's prompt
+    let embed = |text: String| match args.shell {
+        Some(shell) => output::for_shell(&text, shell),
+        None => text,
+    };
 
-fn main() {
-    let pwd = env::current_dir().expect("could not acquire pwd");
-    let arg_path = env::args_os().nth(1).map(Into::<PathBuf>::into);
+    match snapshot {
+        Ok(snapshot) => {
+            // git knows best how long the hash must be, unless told otherwise
+            let options = repo::RenderOptions {
+                hash_len: options.hash_len.or(snapshot.abbrev),
+                ..options
+            };
+            let result = snapshot.prompt();
+            let set_title = args.set_title || args.output == cli::Output::Title;
+            let record_history =
+                !args.sandbox && config.get_bool("history.enabled").unwrap_or(false);
+
+            let toplevel = (!args.sandbox && (set_title || record_history))
+                .then(|| util::try_get_toplevel(&path).ok().flatten())
+                .flatten()
+                .unwrap_or_else(|| path.to_path_buf());
+
+            // only decorate the prompt when something needs attention
+            if repo::Severity::of(&result) >= args.min_severity {
+                if set_title {
+                    print!("{}", embed(output::title(&toplevel, &result)));
+                }
 
-    // this will return `pwd` if `arg_path` was `None`
-    let path = util::path_rel_to_abs(&pwd, arg_path.as_deref());
-    match get_prompt(&*path) {
-        Ok(result) => println!("{:#}", result),
+                match args.output {
+                    cli::Output::Prompt => {
+                        let prompt = match args.max_width {
+                            Some(max_width) => {
+                                output::fit(&snapshot, &options, template.as_ref(), max_width)
+                            }
+                            None => snapshot.render_with(&options, template.as_ref()),
+                        };
+                        // measured before the shell's markers, which take no cells either
+                        let width = output::width(&prompt);
+                        println!("{}", embed(prompt));
+                        if args.emit_width {
+                            println!("{width}");
+                        }
+                    }
+                    cli::Output::Tmux | cli::Output::Zsh => {
+                        println!("{}", snapshot.render_with(&options, template.as_ref()))
+                    }
+                    cli::Output::Html => {
+                        let prompt = snapshot.render_with(&options, template.as_ref());
+                        println!("{}", output::html(&prompt))
+                    }
+                    cli::Output::Symbol => {
+                        println!("{}", embed(repo::Severity::of(&result).render(&options)))
+                    }
+                    cli::Output::Title => {}
+                    cli::Output::Tsv => println!("{}", output::tsv(&result)),
+                    cli::Output::Plain => println!("{}", output::plain(&snapshot)),
+                    cli::Output::Env => print!("{}", output::env(&result)),
+                    cli::Output::Ps1 => println!("{}", embed(output::ps1(&snapshot))),
+                    cli::Output::Split => {
+                        let (left, right) = snapshot.render_split(&options);
+                        let separator = if args.null { '\0' } else { '\n' };
+                        println!("{}{separator}{}", embed(left), embed(right))
+                    }
+                    cli::Output::Json if args.emit_hash => {
+                        println!("{}", output::json_with_hash(&snapshot))
+                    }
+                    cli::Output::Json => println!("{}", output::json(&snapshot)),
+                    cli::Output::Starship => {
+                        let prompt = snapshot.render_with(&options, template.as_ref());
+                        print!("{}", output::for_starship(&prompt))
+                    }
+                }
+
+                if args.emit_hash && args.output != cli::Output::Json {
+                    println!("{}", output::hash(&snapshot));
+                }
+            }
+
+            if record_history {
+                if let Err(err) = history::record(&toplevel, &result) {
+                    if debug {
+                        eprintln!("{err:?}");
+                    }
+                }
+            }
+        }
         Err(err) => {
-            println!(
-                "[{}{}error{}]",
-                termion::style::Bold,
-                termion::color::Fg(termion::color::Red),
-                termion::style::Reset
-            );
-
-            if let Some("--debug") = env::args().nth(2).as_deref() {
+            let busy = util::is_index_locked(&path);
+            // the exit code tells starship, which shows nothing then
+            if !starship {
+                let placeholder = if busy {
+                    output::busy(&options)
+                } else {
+                    output::error(&options)
+                };
+                println!("{}", embed(placeholder));
+            }
+
+            if debug {
                 eprintln!("{err:?}");
             }
 
-            process::exit(1)
+            if !busy || starship {
+                process::exit(1)
+            }
         }
     };
 }
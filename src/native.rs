@@ -0,0 +1,141 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{repo, util};
+
+// trimmed content, `None` if missing or unreadable
+fn read(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|content| content.trim_end().to_owned())
+}
+
+// linked worktrees keep refs in the common dir, `commondir` points there relative to the git dir
+fn common_dir(git_dir: &Path) -> PathBuf {
+    match read(&git_dir.join("commondir")) {
+        Some(common_dir) => git_dir.join(common_dir),
+        None => git_dir.to_path_buf(),
+    }
+}
+
+// a loose ref or one in `packed-refs`, lines there are `<hash> <ref>` or `^<peeled>`
+fn resolve(common_dir: &Path, reference: &str) -> Option<String> {
+    read(&common_dir.join(reference)).or_else(|| {
+        read(&common_dir.join("packed-refs"))?
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .find_map(|(hash, name)| (name == reference).then(|| hash.to_owned()))
+    })
+}
+
+// loose refs below `dir`, by their full name
+fn loose_refs(common_dir: &Path, dir: &str, refs: &mut Vec<(String, String)>) {
+    let Ok(entries) = fs::read_dir(common_dir.join(dir)) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let name = format!("{dir}/{}", entry.file_name().to_string_lossy());
+        if entry.path().is_dir() {
+            loose_refs(common_dir, &name, refs);
+        } else if let Some(hash) = read(&entry.path()) {
+            refs.push((hash, name));
+        }
+    }
+}
+
+// a branch pointing at `reference` if it is a commit, like `show-ref` does for the full status
+fn conflict_ref(common_dir: &Path, reference: &str) -> repo::ConflictRef {
+    if let Some(branch) = reference.strip_prefix("refs/heads/") {
+        return repo::ConflictRef::branch(branch.to_owned());
+    }
+
+    let mut refs = vec![];
+    loose_refs(common_dir, "refs/heads", &mut refs);
+    let packed = read(&common_dir.join("packed-refs")).unwrap_or_default();
+    refs.extend(
+        packed
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .map(|(hash, name)| (hash.to_owned(), name.to_owned())),
+    );
+
+    let branch = refs.iter().find_map(|(hash, name)| {
+        (hash == reference)
+            .then(|| name.strip_prefix("refs/heads/"))
+            .flatten()
+    });
+
+    match branch {
+        Some(branch) => repo::ConflictRef::branch(branch.to_owned()),
+        None => repo::ConflictRef::commit(reference.to_owned()),
+    }
+}
+
+/// What can be read straight from the git dir of the repo at `path` without spawning git or
+/// writing anything: HEAD, a merge or rebase in progress and the stash, see `--sandbox`.
+///
+/// The working tree is never looked at and the upstream is unknown, both need git itself, render
+/// sparse to not claim there is none.
+pub fn get_snapshot(path: &Path) -> Result<repo::RepoSnapshot, Box<dyn Error>> {
+    let git_dir = util::find_git_dir(path).ok_or("not a git repository")?;
+    let common_dir = common_dir(&git_dir);
+
+    // ref: refs/heads/<branch> | <commit>
+    let head = read(&git_dir.join("HEAD")).ok_or("could not read HEAD")?;
+    let (branch, commit) = match head.strip_prefix("ref: ") {
+        Some(reference) => (
+            reference.strip_prefix("refs/heads/").map(str::to_owned),
+            resolve(&common_dir, reference),
+        ),
+        None => (None, Some(head)),
+    };
+
+    let mut snapshot = repo::RepoSnapshot {
+        measured_at: util::unix_now(),
+        branch,
+        worktree: git_dir != common_dir,
+        // one reflog line per entry
+        stash: read(&common_dir.join("logs/refs/stash")).map_or(0, |log| log.lines().count()),
+        ..Default::default()
+    };
+
+    let Some(commit) = commit else {
+        return Ok(snapshot);
+    };
+
+    // a detached head is shown as its hash, not as a branch that happens to point at it
+    let head = match &snapshot.branch {
+        Some(branch) => repo::ConflictRef::branch(branch.clone()),
+        None => repo::ConflictRef::commit(commit.clone()),
+    };
+    // an octopus MERGE_HEAD lists every merged commit, show the first
+    let picked = |kind, file| {
+        let target = read(&git_dir.join(file))?;
+        let target = target.lines().next().unwrap_or_default();
+        Some((kind, conflict_ref(&common_dir, target)))
+    };
+
+    let merged = picked(repo::ConflictKind::Merge, "MERGE_HEAD");
+    snapshot.operation = match (merged, util::try_get_rebase_state(&git_dir)?) {
+        (None, Some((head_name, onto))) => Some(repo::Operation {
+            kind: repo::ConflictKind::Rebase,
+            source: conflict_ref(&common_dir, onto.as_deref().unwrap_or(&commit)),
+            target: conflict_ref(&common_dir, head_name.as_deref().unwrap_or(&commit)),
+        }),
+        (merged, _) => merged
+            .or_else(|| picked(repo::ConflictKind::CherryPick, "CHERRY_PICK_HEAD"))
+            .or_else(|| picked(repo::ConflictKind::Revert, "REVERT_HEAD"))
+            .map(|(kind, target)| repo::Operation {
+                kind,
+                source: head,
+                target,
+            }),
+    };
+
+    snapshot.commit = Some(commit);
+    Ok(snapshot)
+}
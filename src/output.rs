@@ -0,0 +1,784 @@
+use std::{
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::{
+    cache, cli,
+    repo::{self, Change, Changes},
+    util,
+};
+
+/// Printed in place of the prompt if the status could not be acquired while another process holds
+/// the index lock.
+pub fn busy(options: &repo::RenderOptions) -> String {
+    let mut out = String::from("[");
+    let _ = options.paint(&mut out, repo::Role::Busy, "busy");
+    out.push(']');
+    out
+}
+
+/// Printed in place of the prompt if the status could not be acquired.
+pub fn error(options: &repo::RenderOptions) -> String {
+    let mut out = String::from("[");
+    let _ = options.paint(&mut out, repo::Role::Failed, "error");
+    out.push(']');
+    out
+}
+
+// the length of the escape sequence `rest` starts with, CSI up to its final byte, OSC up to BEL or
+// ST, anything else is ESC and one char
+fn escape_len(rest: &str) -> usize {
+    let mut chars = rest.char_indices().skip(1);
+    match chars.next() {
+        Some((_, '[')) => chars
+            .find(|&(_, c)| ('@'..='~').contains(&c))
+            .map_or(rest.len(), |(i, c)| i + c.len_utf8()),
+        Some((_, ']')) => {
+            let end = rest.find(['\x07', '\x1b']).filter(|&i| i > 1);
+            match end.map(|i| (i, rest.as_bytes()[i])) {
+                Some((i, b'\x07')) => i + 1,
+                Some((i, _)) => (i + 2).min(rest.len()),
+                None => rest.len(),
+            }
+        }
+        Some((i, c)) => i + c.len_utf8(),
+        None => rest.len(),
+    }
+}
+
+/// The terminal cells `text` takes, escape sequences take none, see `--emit-width`.
+pub fn width(text: &str) -> usize {
+    let mut width = 0;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let plain = rest.find('\x1b').unwrap_or(rest.len());
+        width += rest[..plain].chars().map(util::char_width).sum::<usize>();
+        rest = &rest[plain..];
+
+        if !rest.is_empty() {
+            rest = &rest[escape_len(rest)..];
+        }
+    }
+    width
+}
+
+/// The prompt as wide as `max_width` cells at most, see `--max-width`.
+///
+/// Less important parts go first: whatever follows the prompt itself, then the upstream and
+/// divergence, then the kinds of changes and last the end of the branch name. Anything which does
+/// not fit even then is printed anyway.
+pub fn fit(
+    snapshot: &repo::RepoSnapshot,
+    options: &repo::RenderOptions,
+    template: Option<&repo::Template>,
+    max_width: usize,
+) -> String {
+    let full = snapshot.render_with(options, template);
+    if width(&full) <= max_width || snapshot.inside_git_dir {
+        return full;
+    }
+
+    let prompt = snapshot.prompt();
+    let render = |options: &repo::RenderOptions| match template {
+        Some(template) => prompt.render_template(template, options),
+        None => prompt.render(options),
+    };
+
+    let mut options = *options;
+    let mut out = render(&options);
+    for shorten in [
+        |options: &mut repo::RenderOptions| options.sparse = true,
+        |options: &mut repo::RenderOptions| options.totals = true,
+    ] {
+        if width(&out) <= max_width {
+            return out;
+        }
+
+        shorten(&mut options);
+        out = render(&options);
+    }
+
+    // cut the branch name by as much as is missing, the ellipsis is within the length but always
+    // keep a cell of it
+    let branch = snapshot
+        .branch
+        .as_deref()
+        .map_or(0, |branch| branch.chars().count())
+        .min(options.branch_len.unwrap_or(usize::MAX));
+    let excess = width(&out).saturating_sub(max_width);
+    if excess != 0 && branch != 0 {
+        options.branch_len = Some(branch.saturating_sub(excess).max(1));
+        out = render(&options);
+    }
+
+    out
+}
+
+// what the shell would expand in its prompt, bash decodes backslash escapes first and then
+// expands parameters and commands (`promptvars`), so both need their escape
+fn push_escaped(out: &mut String, shell: cli::Shell, text: &str) {
+    for c in text.chars() {
+        match (shell, c) {
+            (cli::Shell::Zsh, '%') => out.push_str("%%"),
+            (cli::Shell::Bash, '\\') => out.push_str("\\\\\\\\"),
+            (cli::Shell::Bash, '$') => out.push_str("\\\\$"),
+            (cli::Shell::Bash, '`') => out.push_str("\\\\`"),
+            (_, c) => out.push(c),
+        }
+    }
+}
+
+/// `text` ready to be embedded into the prompt of `shell`, runs of escape sequences marked as
+/// zero width and the shell's own prompt escapes escaped.
+pub fn for_shell(text: &str, shell: cli::Shell) -> String {
+    let (open, close) = match shell {
+        cli::Shell::Zsh => ("%{", "%}"),
+        cli::Shell::Bash => ("\\[", "\\]"),
+        cli::Shell::Fish => return text.to_owned(),
+    };
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while !rest.is_empty() {
+        let plain = rest.find('\x1b').unwrap_or(rest.len());
+        push_escaped(&mut out, shell, &rest[..plain]);
+        rest = &rest[plain..];
+
+        // consecutive escapes share one wrapper, they may contain `%` in urls or `\` in ST
+        let mut escapes = 0;
+        while rest[escapes..].starts_with('\x1b') {
+            escapes += escape_len(&rest[escapes..]);
+        }
+
+        if escapes != 0 {
+            out.push_str(open);
+            push_escaped(&mut out, shell, &rest[..escapes]);
+            out.push_str(close);
+            rest = &rest[escapes..];
+        }
+    }
+
+    out
+}
+
+// starship's names for the 16 colors of `38;5;<n>`, the others go by their number
+const STARSHIP_COLORS: [&str; 16] = [
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "purple",
+    "cyan",
+    "white",
+    "bright-black",
+    "bright-red",
+    "bright-green",
+    "bright-yellow",
+    "bright-blue",
+    "bright-purple",
+    "bright-cyan",
+    "bright-white",
+];
+
+// the color of an extended `38` or `48`, `5;<n>` or `2;<r>;<g>;<b>`, consuming its parameters so
+// none of them is taken for an SGR of its own
+fn extended_color<'p>(params: &mut impl Iterator<Item = &'p str>) -> Option<String> {
+    match params.next()? {
+        "5" => {
+            let n: u8 = params.next()?.parse().ok()?;
+            Some(
+                STARSHIP_COLORS
+                    .get(n as usize)
+                    .map_or_else(|| n.to_string(), |name| name.to_string()),
+            )
+        }
+        "2" => {
+            let rgb: Vec<Option<u8>> = params.take(3).map(|c| c.parse().ok()).collect();
+            match rgb[..] {
+                [Some(r), Some(g), Some(b)] => Some(format!("#{r:02x}{g:02x}{b:02x}")),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// the style after the SGR parameters `params`, as far as starship can express it
+fn apply_sgr(params: &str, bold: &mut bool, fg: &mut Option<String>) {
+    let mut params = params.split(';');
+    while let Some(param) = params.next() {
+        match param {
+            "" | "0" => (*bold, *fg) = (false, None),
+            "1" => *bold = true,
+            "22" => *bold = false,
+            "39" => *fg = None,
+            "38" => *fg = extended_color(&mut params),
+            // backgrounds are never rendered, only skipped over
+            "48" => {
+                extended_color(&mut params);
+            }
+            _ => match param.parse::<usize>() {
+                Ok(n @ 30..=37) => *fg = Some(STARSHIP_COLORS[n - 30].to_owned()),
+                Ok(n @ 90..=97) => *fg = Some(STARSHIP_COLORS[n - 90 + 8].to_owned()),
+                _ => {}
+            },
+        }
+    }
+}
+
+// `$`, `\`, brackets and parentheses mean something in starship format strings
+fn push_starship_escaped(out: &mut String, text: &str) {
+    for c in text.chars() {
+        if matches!(c, '$' | '\\' | '[' | ']' | '(' | ')') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+}
+
+/// `text` as a starship format string, styled runs as `[text](style)` tokens instead of ANSI
+/// escapes and everything else escaped, see `--output=starship`.
+///
+/// Escapes starship has no token for, e.g. OSC 8 links, are dropped.
+pub fn for_starship(text: &str) -> String {
+    let (mut bold, mut fg) = (false, None);
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let plain = rest.find('\x1b').unwrap_or(rest.len());
+        if plain != 0 {
+            let style: Vec<&str> = bold
+                .then_some("bold")
+                .into_iter()
+                .chain(fg.as_deref())
+                .collect();
+            if style.is_empty() {
+                push_starship_escaped(&mut out, &rest[..plain]);
+            } else {
+                out.push('[');
+                push_starship_escaped(&mut out, &rest[..plain]);
+                out.push_str("](");
+                out.push_str(&style.join(" "));
+                out.push(')');
+            }
+        }
+        rest = &rest[plain..];
+
+        if !rest.is_empty() {
+            let len = escape_len(rest);
+            if let Some(params) = rest[..len]
+                .strip_prefix("\x1b[")
+                .and_then(|sgr| sgr.strip_suffix('m'))
+            {
+                apply_sgr(params, &mut bold, &mut fg);
+            }
+            rest = &rest[len..];
+        }
+    }
+
+    out
+}
+
+/// `<repo>:<head>[<state>]` as an OSC 0 sequence, which sets both the window and the tab title,
+/// terminated by ST.
+pub fn title(repo: &Path, prompt: &repo::Prompt) -> String {
+    let name = repo
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_else(|| repo.to_string_lossy());
+
+    let title = format!(
+        "{name}:{}[{}]",
+        prompt.head_name(),
+        repo::Severity::of(prompt).name()
+    );
+
+    // a control char would end the sequence early
+    let title: String = title.chars().filter(|c| !c.is_control()).collect();
+    format!("\x1b]0;{title}\x1b\\")
+}
+
+/// A single tab separated line without header, for scripting with `awk` and friends.
+///
+/// The columns are fixed, new columns are only ever appended:
+///
+/// | # | column    | value                                            |
+/// |---|-----------|--------------------------------------------------|
+/// | 1 | branch    | branch name, tag or commit if detached           |
+/// | 2 | upstream  | `<remote>/<branch>` or empty                     |
+/// | 3 | ahead     | commits ahead of upstream                        |
+/// | 4 | behind    | commits behind upstream                          |
+/// | 5 | staged    | changed paths in the index                       |
+/// | 6 | unstaged  | changed tracked paths in the working tree        |
+/// | 7 | untracked | untracked paths                                  |
+/// | 8 | conflicts | conflicted paths                                 |
+/// | 9 | stash     | stash entries                                    |
+/// | 10| operation | `merge`, `rebase` or empty                       |
+pub fn tsv(prompt: &repo::Prompt) -> String {
+    columns(prompt).map(|(_, value)| value).join("\t")
+}
+
+/// The [`tsv`] columns as `GIT_PROMPT_<COLUMN>='<value>'` lines to `eval` in sh, bash or zsh,
+/// e.g. `GIT_PROMPT_BRANCH='main'`, see `--output=env`.
+///
+/// Values are single quoted, so nothing in them is expanded, and every variable is always set.
+pub fn env(prompt: &repo::Prompt) -> String {
+    let mut out = String::new();
+    for (column, value) in columns(prompt) {
+        let value = value.replace('\'', "'\\''");
+        out.push_str(&format!(
+            "GIT_PROMPT_{}='{value}'\n",
+            column.to_ascii_uppercase()
+        ));
+    }
+    out
+}
+
+fn columns(prompt: &repo::Prompt) -> [(&'static str, String); 10] {
+    let branch = prompt.branch();
+    let upstream = branch
+        .and_then(repo::Branch::remote)
+        .map(repo::RemoteBranch::name)
+        .unwrap_or_default();
+    let (ahead, behind) = branch
+        .and_then(repo::Branch::divergence)
+        .map(repo::Divergence::ahead_behind)
+        .unwrap_or_default();
+
+    let untracked = prompt
+        .working_tree()
+        .map(|w| w[Change::Untracked] + w[Change::UntrackedDir])
+        .unwrap_or(0);
+    let unstaged = prompt.working_tree().map(Changes::total).unwrap_or(0) - untracked;
+    let staged = prompt.index().map(Changes::total).unwrap_or(0);
+
+    [
+        ("branch", prompt.head_name()),
+        ("upstream", upstream),
+        ("ahead", ahead.to_string()),
+        ("behind", behind.to_string()),
+        ("staged", staged.to_string()),
+        ("unstaged", unstaged.to_string()),
+        ("untracked", untracked.to_string()),
+        ("conflicts", prompt.conflicts().to_string()),
+        ("stash", prompt.stash().to_string()),
+        (
+            "operation",
+            prompt
+                .operation()
+                .map(repo::ConflictKind::name)
+                .unwrap_or_default()
+                .to_owned(),
+        ),
+    ]
+}
+
+/// What bash's `__git_ps1` prints with `GIT_PS1_SHOWDIRTYSTATE`, `GIT_PS1_SHOWSTASHSTATE`,
+/// `GIT_PS1_SHOWUNTRACKEDFILES` and `GIT_PS1_SHOWUPSTREAM=auto` set, e.g. ` (main *+$%|MERGING<>)`,
+/// see `--output=ps1`.
+///
+/// `*` unstaged, `+` staged, `#` no commits yet, `$` stashed, `%` untracked and `<`, `>`, `<>` or
+/// `=` for the upstream, detached heads are the tag or the short hash in parentheses.
+pub fn ps1(snapshot: &repo::RepoSnapshot) -> String {
+    if snapshot.inside_git_dir {
+        return " (GIT_DIR!)".to_owned();
+    }
+
+    // a rebase detaches HEAD, `__git_ps1` shows the branch being rebased
+    let rebased = snapshot
+        .operation
+        .as_ref()
+        .filter(|operation| operation.kind == repo::ConflictKind::Rebase)
+        .and_then(|operation| match &operation.target {
+            repo::ConflictRef::Branch(branch) => Some(branch.local().to_owned()),
+            repo::ConflictRef::Commit(_) => None,
+        });
+
+    let head = match (&snapshot.branch, rebased, &snapshot.commit, &snapshot.tag) {
+        (Some(branch), _, _, _) => branch.clone(),
+        (None, Some(branch), _, _) => branch,
+        (None, None, _, Some(tag)) => format!("({tag})"),
+        (None, None, Some(commit), None) => format!("({}...)", &commit[..commit.len().min(7)]),
+        (None, None, None, None) => "(unknown)".to_owned(),
+    };
+
+    let untracked =
+        snapshot.working_tree[Change::Untracked] + snapshot.working_tree[Change::UntrackedDir];
+    let unstaged = snapshot.working_tree.total() - untracked + snapshot.conflicts.total();
+
+    let mut flags = String::new();
+    if unstaged != 0 {
+        flags.push('*');
+    }
+    if snapshot.commit.is_none() {
+        flags.push('#');
+    } else if snapshot.index.any() || snapshot.conflicts.any() {
+        // unmerged paths differ from HEAD in both the working tree and the index
+        flags.push('+');
+    }
+    if snapshot.stash != 0 {
+        flags.push('$');
+    }
+    if untracked != 0 {
+        flags.push('%');
+    }
+
+    // the upstream counts as a flag, the operation does not
+    flags.push_str(
+        match (&snapshot.upstream, snapshot.ahead, snapshot.behind) {
+            (None, _, _) => "",
+            (Some(_), _, _) if snapshot.upstream_gone => "",
+            (Some(_), 0, 0) => "=",
+            (Some(_), _, 0) => ">",
+            (Some(_), 0, _) => "<",
+            (Some(_), _, _) => "<>",
+        },
+    );
+
+    let operation = match snapshot.operation.as_ref().map(|operation| &operation.kind) {
+        Some(repo::ConflictKind::Merge) => "|MERGING",
+        Some(repo::ConflictKind::Rebase) => "|REBASE",
+        Some(repo::ConflictKind::CherryPick) => "|CHERRY-PICKING",
+        Some(repo::ConflictKind::Revert) => "|REVERTING",
+        Some(repo::ConflictKind::Other) => "",
+        None => "",
+    };
+
+    let separator = if flags.is_empty() { "" } else { " " };
+    format!(" ({head}{separator}{flags}{operation})")
+}
+
+/// The built-in layout as plain ASCII for scripts and editor status lines, see `--output=plain`.
+///
+/// Neither `format`, the theme nor any glyph the user configured apply and there are no escape
+/// sequences, so the grammar is stable across configs and releases:
+///
+/// ```text
+/// prompt   = head [" wt"] [" :: s[" n "]"] [" ::" [" [!" n "]"] [" w[" changes "]"]
+///            [" i[" changes "]"] [" sub[" submodules "]"]] *(" " extra)
+/// head     = branch "[" upstream "]" ["[" div "]"] | branch "[-]" | branch "[-:" base "][" div "]"
+///          | tag | hash | describe | source " <- " target | target " -> " source
+///          | "[headless]" | branch " (no commits)"
+/// upstream = remote "/" ("~" | name) [" x"]
+/// div      = [">" n] ["<" n]
+/// changes  = *(("+" | "~" | "-" | "*" | "&" | "?" | "@" | "%" | "%/") n)
+/// ```
+///
+/// `~` stands for an upstream named like the local branch and `x` for one which is gone, which
+/// has no divergence. Branches are followed by `->[<push>][<div>]`, `[<age>]`, `(+<commits>)`,
+/// `=<stacked>` and ` <tag>` and the prompt by extras, e.g. `stale:3d` or `ci:ok`, if the config
+/// enables them. Inside the git dir it is `[.git]`.
+pub fn plain(snapshot: &repo::RepoSnapshot) -> String {
+    snapshot.render(&repo::RenderOptions {
+        ascii: true,
+        ..Default::default()
+    })
+}
+
+/// `prompt` rendered with [`repo::Markup::Html`] as a single element, e.g. for documentation or
+/// web terminals, styled by [`stylesheet`].
+pub fn html(prompt: &str) -> String {
+    format!("<span class=\"epg\">{prompt}</span>")
+}
+
+/// CSS for `--output=html`, the built-in look of each [`repo::Role`] as `.epg-<role>`.
+///
+/// Colors are `var(--epg-<color>)` so pages can swap in their own palette, e.g. that of the
+/// terminal theme the rest of the page shows.
+pub fn stylesheet() -> String {
+    let mut out = String::from(".epg { font-family: monospace; white-space: pre }\n");
+    for role in repo::Role::ALL {
+        let style = role.style();
+        let declarations: Vec<String> = style
+            .fg
+            .map(|color| format!("color: {}", color.css()))
+            .into_iter()
+            .chain(style.bold.then(|| "font-weight: bold".to_owned()))
+            .collect();
+        if !declarations.is_empty() {
+            out.push_str(&format!(
+                ".epg-{} {{ {} }}\n",
+                role.name(),
+                declarations.join("; ")
+            ));
+        }
+    }
+    out
+}
+
+/// `<path>\0<output>\0`, safe for paths containing whitespace or newlines, flushed so consumers
+/// can act on each repo as soon as it is done.
+pub fn record(
+    out: &mut impl Write,
+    path: &Path,
+    snapshot: Option<&repo::RepoSnapshot>,
+    output: cli::Output,
+    options: &repo::RenderOptions,
+) -> io::Result<()> {
+    out.write_all(path.as_os_str().as_encoded_bytes())?;
+    out.write_all(b"\0")?;
+
+    match (snapshot, output) {
+        (None, _) if util::is_index_locked(path) => write!(out, "{}", busy(options))?,
+        (None, _) => write!(out, "{}", error(options))?,
+        (Some(snapshot), cli::Output::Prompt) => {
+            out.write_all(snapshot.render(options).as_bytes())?
+        }
+        (Some(snapshot), cli::Output::Tmux) => write!(
+            out,
+            "{}",
+            snapshot.render(&repo::RenderOptions {
+                markup: repo::Markup::Tmux,
+                ..*options
+            })
+        )?,
+        (Some(snapshot), cli::Output::Zsh) => write!(
+            out,
+            "{}",
+            snapshot.render(&repo::RenderOptions {
+                markup: repo::Markup::Zsh,
+                ..*options
+            })
+        )?,
+        (Some(snapshot), cli::Output::Html) => write!(
+            out,
+            "{}",
+            html(&snapshot.render(&repo::RenderOptions {
+                markup: repo::Markup::Html,
+                ..*options
+            }))
+        )?,
+        (Some(snapshot), cli::Output::Symbol) => write!(
+            out,
+            "{}",
+            repo::Severity::of(&snapshot.prompt()).render(options)
+        )?,
+        (Some(snapshot), cli::Output::Title) => write!(out, "{}", title(path, &snapshot.prompt()))?,
+        (Some(snapshot), cli::Output::Tsv) => write!(out, "{}", tsv(&snapshot.prompt()))?,
+        (Some(snapshot), cli::Output::Plain) => write!(out, "{}", plain(snapshot))?,
+        (Some(snapshot), cli::Output::Env) => write!(out, "{}", env(&snapshot.prompt()))?,
+        (Some(snapshot), cli::Output::Ps1) => write!(out, "{}", ps1(snapshot))?,
+        // NUL already ends the record
+        (Some(snapshot), cli::Output::Split) => {
+            let (left, right) = snapshot.render_split(options);
+            write!(out, "{left}\n{right}")?
+        }
+        (Some(snapshot), cli::Output::Json) => write!(out, "{}", json(snapshot))?,
+        (Some(snapshot), cli::Output::Starship) => {
+            write!(out, "{}", for_starship(&snapshot.render(options)))?
+        }
+    }
+
+    out.write_all(b"\0")?;
+    out.flush()
+}
+
+fn json_str(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn json_opt<T>(value: Option<T>, f: impl FnOnce(T) -> String) -> String {
+    value.map(f).unwrap_or_else(|| "null".to_owned())
+}
+
+fn json_counts<'c>(counts: impl IntoIterator<Item = (&'c str, usize)>) -> String {
+    let fields: Vec<_> = counts
+        .into_iter()
+        .map(|(name, count)| format!("{}:{count}", json_str(name)))
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+/// A short hash of the state in `snapshot` which only changes if the state does, for wrappers to
+/// tell whether anything changed since they last rendered.
+///
+/// Ages count as far as they are shown, `3h` stays `3h` for an hour.
+pub fn hash(snapshot: &repo::RepoSnapshot) -> String {
+    let mut state = snapshot.clone();
+    state.measured_at = 0;
+    // how the state was measured, not the state itself
+    state.warnings.clear();
+
+    let ages: Vec<_> = [
+        &mut state.fetched,
+        &mut state.switched,
+        &mut state.head_age,
+        &mut state.age,
+    ]
+    .into_iter()
+    .map(|age| age.take().map(util::fmt_age).unwrap_or_default())
+    .collect();
+
+    cache::hash_key(format!("{}\0{}", json(&state), ages.join("\0")).as_bytes())
+}
+
+/// [`json`] with the [`hash`] as the last field.
+pub fn json_with_hash(snapshot: &repo::RepoSnapshot) -> String {
+    let json = json(snapshot);
+    let object = json.strip_suffix('}').unwrap_or(&json);
+    format!("{object},\"hash\":{}}}", json_str(&hash(snapshot)))
+}
+
+/// The raw snapshot as a single line JSON object, optional measurements are `null` if disabled.
+pub fn json(snapshot: &repo::RepoSnapshot) -> String {
+    let changes = |changes: &Changes| json_counts(changes.iter().map(|(c, &n)| (c.name(), n)));
+    let operation = snapshot.operation.as_ref().map(|operation| {
+        format!(
+            "{{\"kind\":{},\"source\":{},\"target\":{}}}",
+            json_str(operation.kind.name()),
+            json_str(&operation.source.to_string()),
+            json_str(&operation.target.to_string()),
+        )
+    });
+
+    let diffstat = |stat: repo::DiffStat| {
+        format!(
+            "{{\"insertions\":{},\"deletions\":{}}}",
+            stat.insertions, stat.deletions
+        )
+    };
+
+    let comparison = |comparison: &repo::Comparison| {
+        format!(
+            "{{\"name\":{},\"ahead\":{},\"behind\":{}}}",
+            json_str(&comparison.name),
+            comparison.ahead,
+            comparison.behind,
+        )
+    };
+
+    let forge = snapshot.forge.as_ref().map(|forge| {
+        format!(
+            "{{\"kind\":{},\"url\":{}}}",
+            json_str(forge.kind.name()),
+            json_str(&forge.url)
+        )
+    });
+
+    let fields = [
+        ("measured_at", snapshot.measured_at.to_string()),
+        ("commit", json_opt(snapshot.commit.as_deref(), json_str)),
+        ("branch", json_opt(snapshot.branch.as_deref(), json_str)),
+        ("abbrev", json_opt(snapshot.abbrev, |len| len.to_string())),
+        (
+            "ticket",
+            json_opt(snapshot.ticket.as_ref(), |ticket| json_str(&ticket.id)),
+        ),
+        ("ci", json_opt(snapshot.ci.as_deref(), json_str)),
+        ("pr", json_opt(snapshot.pr.as_deref(), json_str)),
+        ("upstream", json_opt(snapshot.upstream.as_deref(), json_str)),
+        ("forge", json_opt(forge, |forge| forge)),
+        ("upstream_gone", snapshot.upstream_gone.to_string()),
+        ("protected", snapshot.protected.to_string()),
+        ("ahead", snapshot.ahead.to_string()),
+        ("behind", snapshot.behind.to_string()),
+        ("fetched", json_opt(snapshot.fetched, |ago| ago.to_string())),
+        ("push", json_opt(snapshot.push.as_ref(), comparison)),
+        ("fallback", json_opt(snapshot.fallback.as_ref(), comparison)),
+        ("tag", json_opt(snapshot.tag.as_deref(), json_str)),
+        (
+            "describe",
+            json_opt(snapshot.describe.as_ref(), |d| json_str(&d.to_string())),
+        ),
+        ("worktree", snapshot.worktree.to_string()),
+        ("operation", json_opt(operation, |operation| operation)),
+        ("working_tree", changes(&snapshot.working_tree)),
+        ("index", changes(&snapshot.index)),
+        (
+            "hidden",
+            json_opt(snapshot.hidden, |hidden| hidden.to_string()),
+        ),
+        (
+            "unstaged_lines",
+            json_opt(snapshot.unstaged_lines, diffstat),
+        ),
+        ("staged_lines", json_opt(snapshot.staged_lines, diffstat)),
+        ("submodules", json_counts(snapshot.submodules.counts())),
+        ("conflicts", json_counts(snapshot.conflicts.counts())),
+        ("stash", snapshot.stash.to_string()),
+        (
+            "stash_subject",
+            json_opt(snapshot.stash_subject.as_ref(), |(branch, subject)| {
+                format!(
+                    "{{\"branch\":{},\"subject\":{}}}",
+                    json_str(branch),
+                    json_str(subject)
+                )
+            }),
+        ),
+        (
+            "ignored",
+            json_opt(snapshot.ignored, |ignored| ignored.to_string()),
+        ),
+        (
+            "switched",
+            json_opt(snapshot.switched, |ago| ago.to_string()),
+        ),
+        ("subject", json_opt(snapshot.subject.as_deref(), json_str)),
+        (
+            "signature",
+            json_opt(snapshot.signature, |status| json_str(&status.to_string())),
+        ),
+        (
+            "head_age",
+            json_opt(snapshot.head_age, |age| age.to_string()),
+        ),
+        ("age", json_opt(snapshot.age, |age| age.to_string())),
+        (
+            "commits",
+            json_opt(snapshot.commits, |commits| commits.to_string()),
+        ),
+        (
+            "headers",
+            format!(
+                "{{{}}}",
+                snapshot
+                    .headers
+                    .iter()
+                    .map(|(key, value)| format!("{}:{}", json_str(key), json_str(value)))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+        ),
+        ("inside_git_dir", snapshot.inside_git_dir.to_string()),
+        (
+            "stacked",
+            json_opt(snapshot.stacked, |stacked| stacked.to_string()),
+        ),
+        ("reduced", snapshot.reduced.to_string()),
+        (
+            "warnings",
+            format!(
+                "[{}]",
+                snapshot
+                    .warnings
+                    .iter()
+                    .map(|warning| json_str(warning))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+        ),
+    ];
+
+    let fields: Vec<_> = fields
+        .iter()
+        .map(|(name, value)| format!("{}:{value}", json_str(name)))
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
@@ -0,0 +1,90 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    panic,
+    path::Path,
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use crate::util;
+
+// below this the placeholder would only flicker
+const DELAY: Duration = Duration::from_millis(50);
+
+#[cfg(unix)]
+fn open_tty() -> Option<File> {
+    OpenOptions::new().write(true).open("/dev/tty").ok()
+}
+
+#[cfg(windows)]
+fn open_tty() -> Option<File> {
+    OpenOptions::new().write(true).open("CONOUT$").ok()
+}
+
+#[cfg(not(any(unix, windows)))]
+fn open_tty() -> Option<File> {
+    None
+}
+
+// ref: refs/heads/<branch> | <commit>
+fn read_head(path: &Path) -> Option<String> {
+    let head = fs::read_to_string(util::find_git_dir(path)?.join("HEAD")).ok()?;
+    let head = head.trim_end();
+
+    match head.strip_prefix("ref: ") {
+        Some(reference) => Some(
+            reference
+                .strip_prefix("refs/heads/")
+                .unwrap_or(reference)
+                .to_owned(),
+        ),
+        None => Some(head.get(..7).unwrap_or(head).to_owned()),
+    }
+}
+
+/// Runs `f`, if it takes longer than a moment a placeholder with only the head read straight from
+/// the git dir is drawn on the terminal in the meantime.
+///
+/// The placeholder is erased again using cursor save and restore before returning, so the shell
+/// draws the actual prompt in its place and line editing is not confused by stray output.
+pub fn with_placeholder<T: Send>(path: &Path, f: impl FnOnce() -> T + Send) -> T {
+    thread::scope(|scope| {
+        let (tx, rx) = mpsc::channel();
+        let handle = scope.spawn(move || {
+            let _ = tx.send(f());
+        });
+
+        if let Ok(value) = rx.recv_timeout(DELAY) {
+            return value;
+        }
+
+        let mut tty = open_tty();
+        if let Some(tty) = &mut tty {
+            let head = read_head(path).unwrap_or_default();
+            let _ = write!(
+                tty,
+                "\x1b7{}{head} …{}",
+                termion::style::Faint,
+                termion::style::Reset
+            );
+            let _ = tty.flush();
+        }
+
+        let value = match rx.recv() {
+            Ok(value) => value,
+            Err(_) => match handle.join() {
+                Err(payload) => panic::resume_unwind(payload),
+                Ok(()) => unreachable!("the value is sent before the thread finishes"),
+            },
+        };
+
+        if let Some(tty) = &mut tty {
+            let _ = tty.write_all(b"\x1b8\x1b[J");
+            let _ = tty.flush();
+        }
+
+        value
+    })
+}
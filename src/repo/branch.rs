@@ -1,55 +1,80 @@
-use std::fmt::{Debug, Display};
+use std::{
+    borrow::Cow,
+    fmt::{self, Debug, Display, Write},
+};
 
+use super::{Glyph, RenderOptions, Role, Segment, Tag, Truncation};
+use crate::{ticket::Ticket, util};
+
+/// An upstream branch, `gone` if it was configured but no longer exists on the remote.
 #[derive(Clone, PartialEq, Eq)]
-pub struct RemoteBranch(String, String);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RemoteBranch(String, String, bool);
 
 impl RemoteBranch {
     pub fn new(remote: String, branch: String) -> Self {
-        Self(remote, branch)
+        Self(remote, branch, false)
+    }
+
+    pub fn with_gone(self, gone: bool) -> Self {
+        Self(self.0, self.1, gone)
+    }
+
+    /// `<remote>/<branch>`
+    pub fn name(&self) -> String {
+        format!("{}/{}", self.0, self.1)
+    }
+
+    pub fn branch(&self) -> &str {
+        &self.1
+    }
+
+    pub fn is_gone(&self) -> bool {
+        self.2
     }
 }
 
 impl Debug for RemoteBranch {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "refs/remote/{}/{}", self.0, self.1)
+        write!(f, "refs/remote/{}/{}", self.0, self.1)?;
+
+        if self.2 {
+            f.write_str(" (gone)")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl RemoteBranch {
+    pub fn render_to(&self, out: &mut dyn Write, options: &RenderOptions) -> fmt::Result {
+        options.paint(out, Role::RemoteName, &self.0)?;
+        out.write_char('/')?;
+        // sparse printing
+        let branch = match options.sparse {
+            true => Cow::Borrowed("~"),
+            false => truncate_branch(&self.1, options),
+        };
+        options.paint(out, Role::RemoteName, branch)?;
+
+        if self.2 {
+            out.write_char(' ')?;
+            options.paint(out, Role::Error, options.glyph(Glyph::Gone))?;
+        }
+
+        Ok(())
     }
 }
 
 impl Display for RemoteBranch {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        use termion::{color, style};
-
-        if f.alternate() {
-            write!(
-                f,
-                "{fg}{}{r}/{fg}{}{r}",
-                self.0,
-                // sparse printing
-                if f.sign_aware_zero_pad() {
-                    "~"
-                } else {
-                    &self.1
-                },
-                fg = color::Fg(color::Blue),
-                r = style::Reset
-            )
-        } else {
-            write!(
-                f,
-                "{}/{}",
-                self.0,
-                // sparse printing
-                if f.sign_aware_zero_pad() {
-                    "~"
-                } else {
-                    &self.1
-                }
-            )
-        }
+        let options = RenderOptions::from_formatter(f);
+        self.render_to(f, &options)
     }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Divergence(usize, usize);
 
 impl Divergence {
@@ -76,48 +101,52 @@ impl Debug for Divergence {
     }
 }
 
-impl Display for Divergence {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        use termion::{color, style};
-
+impl Divergence {
+    pub fn render_to(&self, out: &mut dyn Write, options: &RenderOptions) -> fmt::Result {
         let (ahead, behind) = self.ahead_behind();
 
-        if f.alternate() {
-            if self.0 != 0 {
-                write!(
-                    f,
-                    "{fg}{r}{ahead}",
-                    fg = color::Fg(color::Red),
-                    r = style::Reset
-                )?;
-            }
-
-            if self.1 != 0 {
-                write!(
-                    f,
-                    "{fg}{r}{behind}",
-                    fg = color::Fg(color::Red),
-                    r = style::Reset
-                )?;
-            }
-        } else {
-            if self.0 != 0 {
-                write!(f, "{ahead}")?;
-            }
+        if ahead != 0 {
+            options.paint(out, Role::Diverged, options.glyph(Glyph::Ahead))?;
+            write!(out, "{ahead}")?;
+        }
 
-            if self.1 != 0 {
-                write!(f, "{behind}")?;
-            }
+        if behind != 0 {
+            options.paint(out, Role::Diverged, options.glyph(Glyph::Behind))?;
+            write!(out, "{behind}")?;
         }
 
         Ok(())
     }
 }
 
+impl Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let options = RenderOptions::from_formatter(f);
+        self.render_to(f, &options)
+    }
+}
+
 #[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Branch {
     local: String,
     remote: Option<(RemoteBranch, Option<Divergence>)>,
+    // where pushes go if that is not the upstream, boxed as it is rare
+    push: Option<Box<(RemoteBranch, Option<Divergence>)>>,
+    // an issue tracker id in the local name, shown in its place, boxed like `push`
+    ticket: Option<Box<Ticket>>,
+    // the base branch if there is no upstream
+    fallback: Option<Box<(String, Option<Divergence>)>>,
+    // the tag the branch is exactly on
+    tag: Option<Tag>,
+    // seconds since it diverged from the base branch
+    age: Option<u64>,
+    // commits not on the base branch
+    commits: Option<usize>,
+    // branches with commits on top of this one
+    stacked: Option<usize>,
+    // the page of the upstream branch on its forge, see `links.enabled`
+    link: Option<String>,
 }
 
 impl Debug for Branch {
@@ -132,6 +161,14 @@ impl Debug for Branch {
             .field("remote", &self.remote())
             .field("ahead", &ahead)
             .field("behind", &behind)
+            .field("push", &self.push())
+            .field("fallback", &self.fallback.as_deref().map(|(name, _)| name))
+            .field("tag", &self.tag)
+            .field("ticket", &self.ticket)
+            .field("age", &self.age)
+            .field("commits", &self.commits)
+            .field("stacked", &self.stacked)
+            .field("link", &self.link)
             .finish()
     }
 }
@@ -141,70 +178,316 @@ impl Branch {
         Self {
             local,
             remote: remote_diverge,
+            push: None,
+            fallback: None,
+            tag: None,
+            ticket: None,
+            age: None,
+            commits: None,
+            stacked: None,
+            link: None,
+        }
+    }
+
+    pub fn with_push(self, push: Option<(RemoteBranch, Option<Divergence>)>) -> Self {
+        Self {
+            push: push.map(Box::new),
+            ..self
+        }
+    }
+
+    pub fn with_fallback(self, fallback: Option<(String, Option<Divergence>)>) -> Self {
+        Self {
+            fallback: fallback.map(Box::new),
+            ..self
+        }
+    }
+
+    pub fn with_tag(self, tag: Option<Tag>) -> Self {
+        Self { tag, ..self }
+    }
+
+    pub fn with_ticket(self, ticket: Option<Ticket>) -> Self {
+        Self {
+            ticket: ticket.map(Box::new),
+            ..self
         }
     }
 
+    pub fn with_age(self, age: Option<u64>) -> Self {
+        Self { age, ..self }
+    }
+
+    pub fn with_commits(self, commits: Option<usize>) -> Self {
+        Self { commits, ..self }
+    }
+
+    pub fn with_stacked(self, stacked: Option<usize>) -> Self {
+        Self { stacked, ..self }
+    }
+
+    pub fn with_link(self, link: Option<String>) -> Self {
+        Self { link, ..self }
+    }
+
+    pub fn local(&self) -> &str {
+        &self.local
+    }
+
     pub fn remote(&self) -> Option<&RemoteBranch> {
-        self.remote.as_ref().map(|&(ref r, _)| r)
+        self.remote.as_ref().map(|(r, _)| r)
     }
 
     pub fn divergence(&self) -> Option<Divergence> {
-        self.remote.as_ref().map(|&(_, d)| d).flatten()
+        self.remote.as_ref().and_then(|&(_, d)| d)
+    }
+
+    pub fn push(&self) -> Option<&RemoteBranch> {
+        self.push.as_deref().map(|(p, _)| p)
+    }
+
+    pub fn push_divergence(&self) -> Option<Divergence> {
+        self.push.as_deref().and_then(|&(_, d)| d)
     }
 }
 
-impl Display for Branch {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        use termion::{color, style};
+// see `RenderOptions::branch_len`
+fn truncate_branch<'b>(name: &'b str, options: &RenderOptions) -> Cow<'b, str> {
+    let ellipsis = options.glyph(Glyph::Ellipsis);
+    match (options.branch_len, options.truncation) {
+        (Some(len), Truncation::End) => util::truncate(name, len, ellipsis),
+        (Some(len), Truncation::Middle) => util::truncate_middle(name, len, ellipsis),
+        (None, _) => Cow::Borrowed(name),
+    }
+}
 
-        match self.remote() {
-            Some(remote) => {
-                let divergence = self.divergence();
+// `PROJ-123:login`, the id linked with OSC 8 if there is a url and the rest to the branch page
+fn render_ticket(
+    out: &mut dyn Write,
+    options: &RenderOptions,
+    ticket: &Ticket,
+    link: Option<&str>,
+) -> fmt::Result {
+    options.paint_link(out, Role::Ticket, &ticket.id, ticket.url.as_deref())?;
+
+    if !ticket.rest.is_empty() {
+        out.write_char(':')?;
+        let rest = truncate_branch(&ticket.rest, options);
+        options.paint_link(out, Role::BranchName, rest, link)?;
+    }
+
+    Ok(())
+}
+
+// `[]` if level, e.g. `[2]` otherwise
+fn render_divergence(
+    out: &mut dyn Write,
+    options: &RenderOptions,
+    divergence: Option<Divergence>,
+) -> fmt::Result {
+    out.write_char('[')?;
+    match divergence {
+        None => options.paint(out, Role::InSync, "")?,
+        Some(divergence) => divergence.render_to(out, options)?,
+    }
+    out.write_char(']')
+}
+
+impl Branch {
+    // age and commits relative to the base branch, and the branches stacked on top
+    fn render_base(&self, out: &mut dyn Write, options: &RenderOptions) -> fmt::Result {
+        let hidden = options.hidden;
+        if let Some(age) = self.age.filter(|_| !hidden.contains(Segment::Age)) {
+            out.write_char('[')?;
+            options.paint(out, Role::Base, util::fmt_age(age))?;
+            out.write_char(']')?;
+        }
+
+        if let Some(commits) = self.commits.filter(|_| !hidden.contains(Segment::Commits)) {
+            out.write_char('(')?;
+            options.paint(out, Role::Base, format_args!("+{commits}"))?;
+            out.write_char(')')?;
+        }
+
+        if let Some(stacked) = self.stacked.filter(|_| !hidden.contains(Segment::Stacked)) {
+            let glyph = options.glyph(Glyph::Stacked);
+            options.paint(out, Role::Base, format_args!("{glyph}{stacked}"))?;
+        }
+
+        Ok(())
+    }
+
+    // the branch name is implied if it is the same as the local one, unless asked to spell it out
+    fn render_remote(
+        &self,
+        out: &mut dyn Write,
+        options: &RenderOptions,
+        remote: &RemoteBranch,
+    ) -> fmt::Result {
+        remote.render_to(
+            out,
+            &RenderOptions {
+                sparse: !options.full_upstream && remote.1 == self.local,
+                ..*options
+            },
+        )
+    }
+
+    /// A single segment of a [`Template`](super::Template), nothing if it does not apply to
+    /// branches or is not set.
+    pub fn render_segment(
+        &self,
+        segment: Segment,
+        out: &mut dyn Write,
+        options: &RenderOptions,
+    ) -> fmt::Result {
+        let fallback = self.fallback.as_deref();
+        let push = self.push.as_deref();
 
-                if f.alternate() {
-                    write!(f, "{:#}", self.local)?;
-                } else {
-                    write!(f, "{}", self.local)?;
+        match segment {
+            Segment::Branch => {
+                let icon = options.glyph(Glyph::Branch);
+                if !icon.is_empty() {
+                    options.paint(out, Role::BranchName, icon)?;
+                    out.write_char(' ')?;
                 }
 
-                // sparse printing
-                if f.sign_aware_zero_pad() {
-                    return Ok(());
+                match &self.ticket {
+                    Some(ticket) => render_ticket(out, options, ticket, self.link.as_deref()),
+                    None => {
+                        let local = truncate_branch(&self.local, options);
+                        options.paint_link(out, Role::BranchName, local, self.link.as_deref())
+                    }
                 }
+            }
+            Segment::Remote => match self.remote() {
+                Some(remote) => self.render_remote(out, options, remote),
+                None => Ok(()),
+            },
+            Segment::Div => match self.divergence() {
+                Some(divergence) => divergence.render_to(out, options),
+                None => Ok(()),
+            },
+            Segment::Push => match push {
+                Some((push, _)) => self.render_remote(out, options, push),
+                None => Ok(()),
+            },
+            Segment::PushDiv => match push.and_then(|&(_, divergence)| divergence) {
+                Some(divergence) => divergence.render_to(out, options),
+                None => Ok(()),
+            },
+            Segment::Fallback => match fallback {
+                Some((base, _)) => options.paint(out, Role::Muted, base),
+                None => Ok(()),
+            },
+            Segment::FallbackDiv => match fallback.and_then(|&(_, divergence)| divergence) {
+                Some(divergence) => divergence.render_to(out, options),
+                None => Ok(()),
+            },
+            Segment::Age => match self.age {
+                Some(age) => options.paint(out, Role::Base, util::fmt_age(age)),
+                None => Ok(()),
+            },
+            Segment::Commits => match self.commits {
+                Some(commits) => options.paint(out, Role::Base, format_args!("+{commits}")),
+                None => Ok(()),
+            },
+            Segment::Stacked => match self.stacked {
+                Some(stacked) => {
+                    let glyph = options.glyph(Glyph::Stacked);
+                    options.paint(out, Role::Base, format_args!("{glyph}{stacked}"))
+                }
+                None => Ok(()),
+            },
+            Segment::Tag => match &self.tag {
+                Some(tag) => options.paint(out, Role::Tag, &tag.0),
+                None => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    pub fn render_to(&self, out: &mut dyn Write, options: &RenderOptions) -> fmt::Result {
+        self.render_segment(Segment::Branch, out, options)?;
+
+        // sparse printing
+        if options.sparse {
+            return Ok(());
+        }
 
-                match (f.alternate(), remote.1 == self.local) {
-                    (true, false) => write!(f, "[{remote:#}]")?,
-                    (true, true) => write!(f, "[{remote:#0}]")?,
-                    (false, false) => write!(f, "[{remote:}]")?,
-                    (false, true) => write!(f, "[{remote:0}]")?,
+        let hidden = options.hidden;
+        match self.remote() {
+            // nothing worth telling
+            Some(remote)
+                if options.compact
+                    && !remote.is_gone()
+                    && remote.1 == self.local
+                    && self.divergence().is_none() => {}
+            Some(remote) => {
+                if !hidden.contains(Segment::Remote) {
+                    out.write_char('[')?;
+                    self.render_remote(out, options, remote)?;
+                    out.write_char(']')?;
                 }
 
-                match (f.alternate(), divergence) {
-                    (true, None) => write!(f, "[{}{}]", color::Fg(color::Green), style::Reset)?,
-                    (true, Some(divergence)) => write!(f, "[{divergence:#}]")?,
-                    (false, None) => f.write_str("[]")?,
-                    (false, Some(divergence)) => write!(f, "[{divergence}]")?,
+                // there is nothing to diverge from
+                if !remote.is_gone() && !hidden.contains(Segment::Div) {
+                    render_divergence(out, options, self.divergence())?;
                 }
             }
             None => {
-                if f.alternate() {
-                    write!(f, "{:#}", self.local)?;
-                } else {
-                    write!(f, "{}", self.local)?;
+                // no upstream, but drift from the base branch
+                let fallback = self
+                    .fallback
+                    .as_deref()
+                    .filter(|_| !hidden.contains(Segment::Fallback));
+                if !hidden.contains(Segment::Remote) {
+                    out.write_char('[')?;
+                    options.paint(out, Role::RemoteName, '-')?;
+                    if let Some((base, _)) = fallback {
+                        out.write_char(':')?;
+                        options.paint(out, Role::Muted, base)?;
+                    }
+                    out.write_char(']')?;
                 }
 
-                // sparse printing
-                if f.sign_aware_zero_pad() {
-                    return Ok(());
-                }
-                if f.alternate() {
-                    write!(f, "[{}-{}]", color::Fg(color::Blue), style::Reset)?;
-                } else {
-                    f.write_str("[-]")?;
+                if let Some((_, divergence)) =
+                    fallback.filter(|_| !hidden.contains(Segment::FallbackDiv))
+                {
+                    render_divergence(out, options, *divergence)?;
                 }
             }
         }
 
+        // e.g. `→[fork/~][2]` for commits not pushed to the fork yet
+        let push = self
+            .push
+            .as_deref()
+            .filter(|_| !hidden.contains(Segment::Push));
+        if let Some((push, divergence)) = push {
+            options.text(out, options.glyph(Glyph::Push))?;
+            out.write_char('[')?;
+            self.render_remote(out, options, push)?;
+            out.write_char(']')?;
+            if !hidden.contains(Segment::PushDiv) {
+                render_divergence(out, options, *divergence)?;
+            }
+        }
+
+        self.render_base(out, options)?;
+
+        if let Some(tag) = self.tag.as_ref().filter(|_| !hidden.contains(Segment::Tag)) {
+            out.write_char(' ')?;
+            tag.render_to(out, options)?;
+        }
+
         Ok(())
     }
 }
+
+impl Display for Branch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let options = RenderOptions::from_formatter(f);
+        self.render_to(f, &options)
+    }
+}
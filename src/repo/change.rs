@@ -1,71 +1,125 @@
 use std::{
     array,
-    fmt::{Debug, Display},
+    fmt::{self, Debug, Display, Write},
     iter::Enumerate,
     ops::{Index, IndexMut},
     slice,
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+use super::{Glyph, RenderOptions, Role};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Change {
     Add = 0,
     Mod = 1,
     Del = 2,
     Ren = 3,
-    Typ = 4,
+    Cpy = 4,
+    Typ = 5,
+    Sub = 6,
+    Untracked = 7,
+    /// Untracked directories, only counted apart from files with `untracked.dirs`.
+    UntrackedDir = 8,
 }
 
 impl Change {
+    pub const ALL: [Change; 9] = [
+        Change::Add,
+        Change::Mod,
+        Change::Del,
+        Change::Ren,
+        Change::Cpy,
+        Change::Typ,
+        Change::Sub,
+        Change::Untracked,
+        Change::UntrackedDir,
+    ];
+
     fn from_idx(value: usize) -> Self {
         match value {
             0 => Self::Add,
             1 => Self::Mod,
             2 => Self::Del,
             3 => Self::Ren,
-            4 => Self::Typ,
-            x => unreachable!("invalid index, expected 0..=4, got {x}"),
+            4 => Self::Cpy,
+            5 => Self::Typ,
+            6 => Self::Sub,
+            7 => Self::Untracked,
+            8 => Self::UntrackedDir,
+            x => unreachable!("invalid index, expected 0..=8, got {x}"),
         }
     }
 
-    fn fmt_with(&self, value: usize, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        use termion::{color, style};
-
-        if f.alternate() {
-            match self {
-                Change::Add => write!(f, "{}+{value}{}", color::Fg(color::Green), style::Reset),
-                Change::Mod => write!(f, "{}~{value}{}", color::Fg(color::Yellow), style::Reset),
-                Change::Del => write!(f, "{}-{value}{}", color::Fg(color::Red), style::Reset),
-                Change::Ren => write!(f, "{}*{value}{}", color::Fg(color::Cyan), style::Reset),
-                Change::Typ => write!(f, "{}?{value}{}", color::Fg(color::Magenta), style::Reset),
-            }
-        } else {
-            write!(
-                f,
-                "{}{value}",
-                match self {
-                    Change::Add => '+',
-                    Change::Mod => '~',
-                    Change::Del => '-',
-                    Change::Ren => '*',
-                    Change::Typ => '?',
-                }
-            )
+    pub fn name(&self) -> &'static str {
+        match self {
+            Change::Add => "add",
+            Change::Mod => "mod",
+            Change::Del => "del",
+            Change::Ren => "ren",
+            Change::Cpy => "cpy",
+            Change::Typ => "typ",
+            Change::Sub => "sub",
+            Change::Untracked => "untracked",
+            Change::UntrackedDir => "untracked_dirs",
         }
     }
+
+    fn render_with(
+        &self,
+        value: usize,
+        out: &mut dyn Write,
+        options: &RenderOptions,
+    ) -> fmt::Result {
+        let role = match self {
+            Change::Add => Role::Added,
+            Change::Mod => Role::Modified,
+            Change::Del => Role::Removed,
+            Change::Ren => Role::Renamed,
+            Change::Cpy => Role::Copied,
+            Change::Typ => Role::TypeChanged,
+            Change::Sub => Role::Submodule,
+            Change::Untracked | Change::UntrackedDir => Role::Untracked,
+        };
+
+        let symbol = options.glyph(Glyph::Change(*self));
+        options.paint(
+            out,
+            role,
+            format_args!("{symbol}{}", Capped(value, options)),
+        )
+    }
 }
 
-#[derive(Clone, PartialEq, Eq)]
-pub struct Changes([usize; 5]);
+// `99+` for counts above `count.cap`
+struct Capped<'o>(usize, &'o RenderOptions<'o>);
+
+impl Display for Capped<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.1.overrides.and_then(|overrides| overrides.cap()) {
+            Some(cap) if self.0 > cap => write!(f, "{cap}+"),
+            _ => write!(f, "{}", self.0),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Changes([usize; 9]);
 
 impl Changes {
     pub fn new() -> Self {
-        Self([0; 5])
+        Self([0; 9])
     }
 
     pub fn any(&self) -> bool {
         self.iter().any(|(_, &v)| v != 0)
     }
 
+    pub fn total(&self) -> usize {
+        self.0.iter().sum()
+    }
+
     pub fn iter(&self) -> Iter<'_> {
         Iter(self.0.iter().enumerate())
     }
@@ -78,20 +132,56 @@ impl Debug for Changes {
             .field("mod", &self[Change::Mod])
             .field("del", &self[Change::Del])
             .field("ren", &self[Change::Ren])
+            .field("cpy", &self[Change::Cpy])
+            .field("typ", &self[Change::Typ])
+            .field("sub", &self[Change::Sub])
+            .field("untracked", &self[Change::Untracked])
+            .field("untracked_dirs", &self[Change::UntrackedDir])
             .finish()
     }
 }
 
-impl Display for Changes {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for (change, &count) in self.iter().filter(|&(_, &v)| v != 0) {
-            change.fmt_with(count, f)?;
+impl Changes {
+    // counts up to their `count.<change>` threshold are left out
+    fn shown<'c>(
+        &'c self,
+        options: &'c RenderOptions,
+    ) -> impl Iterator<Item = (Change, usize)> + 'c {
+        self.iter().filter_map(move |(change, &count)| {
+            let threshold = options
+                .overrides
+                .map_or(0, |overrides| overrides.threshold(change));
+            (count > threshold).then_some((change, count))
+        })
+    }
+
+    /// Whether [`render_to`](Self::render_to) writes anything, unlike [`any`](Self::any) this
+    /// leaves out counts below their threshold.
+    pub fn any_shown(&self, options: &RenderOptions) -> bool {
+        self.shown(options).next().is_some()
+    }
+
+    pub fn render_to(&self, out: &mut dyn Write, options: &RenderOptions) -> fmt::Result {
+        if options.totals {
+            let total = self.shown(options).map(|(_, count)| count).sum();
+            return options.paint(out, Role::Modified, Capped(total, options));
+        }
+
+        for (change, count) in self.shown(options) {
+            change.render_with(count, out, options)?;
         }
 
         Ok(())
     }
 }
 
+impl Display for Changes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let options = RenderOptions::from_formatter(f);
+        self.render_to(f, &options)
+    }
+}
+
 impl Index<Change> for Changes {
     type Output = usize;
 
@@ -107,7 +197,7 @@ impl IndexMut<Change> for Changes {
 }
 
 pub struct Iter<'a>(Enumerate<slice::Iter<'a, usize>>);
-pub struct IntoIter(Enumerate<array::IntoIter<usize, 5>>);
+pub struct IntoIter(Enumerate<array::IntoIter<usize, 9>>);
 
 impl<'a> Iterator for Iter<'a> {
     type Item = (Change, &'a usize);
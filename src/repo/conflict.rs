@@ -0,0 +1,97 @@
+use std::fmt::{self, Debug, Display, Write};
+
+use super::{Glyph, RenderOptions, Role};
+
+/// Counts of unmerged paths by how both sides changed them.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Conflicts {
+    both_modified: usize,
+    both_added: usize,
+    both_deleted: usize,
+    added: usize,
+    deleted: usize,
+}
+
+impl Conflicts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // UU       both modified
+    // AA       both added
+    // DD       both deleted
+    // AU, UA   added by one side
+    // DU, UD   deleted by one side, modified by the other
+    pub fn add(&mut self, xy: (char, char)) {
+        match xy {
+            ('U', 'U') => self.both_modified += 1,
+            ('A', 'A') => self.both_added += 1,
+            ('D', 'D') => self.both_deleted += 1,
+            ('A', 'U') | ('U', 'A') => self.added += 1,
+            _ => self.deleted += 1,
+        }
+    }
+
+    /// Counts by the short label of their kind, `uu`, `aa`, `dd`, `au` and `du`.
+    pub fn counts(&self) -> [(&'static str, usize); 5] {
+        [
+            ("uu", self.both_modified),
+            ("aa", self.both_added),
+            ("dd", self.both_deleted),
+            ("au", self.added),
+            ("du", self.deleted),
+        ]
+    }
+
+    pub fn total(&self) -> usize {
+        self.both_modified + self.both_added + self.both_deleted + self.added + self.deleted
+    }
+
+    pub fn any(&self) -> bool {
+        self.total() != 0
+    }
+}
+
+impl Debug for Conflicts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Conflicts")
+            .field("uu", &self.both_modified)
+            .field("aa", &self.both_added)
+            .field("dd", &self.both_deleted)
+            .field("au", &self.added)
+            .field("du", &self.deleted)
+            .finish()
+    }
+}
+
+// !3 or !3: uu2 dd1
+impl Conflicts {
+    pub fn render_to(&self, out: &mut dyn Write, options: &RenderOptions) -> fmt::Result {
+        options.paint(
+            out,
+            Role::Conflict,
+            format_args!("{}{}", options.glyph(Glyph::Conflict), self.total()),
+        )?;
+
+        if !options.detailed_conflicts {
+            return Ok(());
+        }
+
+        out.write_str(":")?;
+
+        for (label, count) in self.counts().into_iter().filter(|&(_, count)| count != 0) {
+            out.write_char(' ')?;
+            options.paint(out, Role::Error, format_args!("{label}{count}"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Conflicts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let options = RenderOptions::from_formatter(f);
+        self.render_to(f, &options)
+    }
+}
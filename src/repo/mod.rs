@@ -1,20 +1,49 @@
 use std::{
-    fmt::{Debug, Display, Write},
+    fmt::{self, Debug, Display, Write},
     ops::Deref,
 };
 
+use crate::util;
+
 mod branch;
 pub use branch::{Branch, Divergence, RemoteBranch};
 
 mod change;
 pub use change::{Change, Changes};
 
+mod conflict;
+pub use conflict::Conflicts;
+
+mod render;
+pub use render::{HintStyle, Markup, RenderOptions, Truncation};
+
+mod style;
+pub use style::{Background, Color, Glyph, Overrides, Role, Style, Theme};
+
+mod snapshot;
+pub use snapshot::{Comparison, DiffStat, Hint, Operation, RepoSnapshot};
+
+mod severity;
+pub use severity::Severity;
+
+mod submodule;
+pub use submodule::Submodules;
+
+mod template;
+pub use template::{Segment, Segments, Template};
+
+// the page of the commit on the forge of the upstream, see `links.enabled`
 #[derive(Clone, PartialEq, Eq)]
-pub struct Commit(String);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Commit(String, Option<String>);
 
 impl Commit {
     pub fn new(hash: String) -> Self {
-        Self(hash)
+        Self(hash, None)
+    }
+
+    pub fn with_link(self, link: Option<String>) -> Self {
+        Self(self.0, link)
     }
 }
 
@@ -24,29 +53,21 @@ impl Debug for Commit {
     }
 }
 
+impl Commit {
+    pub fn render_to(&self, out: &mut dyn Write, options: &RenderOptions) -> fmt::Result {
+        // by chars, the hash may come from anywhere, e.g. stdin of `epb-prompt-render`
+        let end = options
+            .hash_len
+            .map_or(self.0.len(), |len| util::char_offset(&self.0, len));
+
+        options.paint_link(out, Role::Commit, &self.0[..end], self.1.as_deref())
+    }
+}
+
 impl Display for Commit {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        use termion::{color, style};
-
-        // don't use width here because that is expected to add whitespace for values longer than
-        // our fmt?
-        let len = f
-            .width()
-            .map(|p| Ord::min(p, self.0.len()))
-            .unwrap_or(self.0.len());
-
-        if f.alternate() {
-            write!(
-                f,
-                "{}{}{hash}{}",
-                style::Bold,
-                color::Fg(color::Yellow),
-                style::Reset,
-                hash = &self.0[..len]
-            )
-        } else {
-            write!(f, "{hash}", hash = &self.0[..len])
-        }
+        let options = RenderOptions::from_formatter(f);
+        self.render_to(f, &options)
     }
 }
 
@@ -59,15 +80,34 @@ impl Deref for Commit {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ConflictKind {
     Merge,
     Rebase,
+    CherryPick,
+    Revert,
+    /// Conflicts without an operation in progress, e.g. after `git stash pop`.
+    Other,
+}
+
+impl ConflictKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConflictKind::Merge => "merge",
+            ConflictKind::Rebase => "rebase",
+            ConflictKind::CherryPick => "cherry-pick",
+            ConflictKind::Revert => "revert",
+            ConflictKind::Other => "conflict",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ConflictRef {
     Commit(Commit),
-    Branch(Branch),
+    // boxed, a branch is much larger than a commit
+    Branch(Box<Branch>),
 }
 
 impl ConflictRef {
@@ -76,27 +116,35 @@ impl ConflictRef {
     }
 
     pub fn branch(local: String) -> Self {
-        Self::Branch(Branch::new(local, None))
+        Self::Branch(Box::new(Branch::new(local, None)))
     }
 }
 
-impl Display for ConflictRef {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl ConflictRef {
+    pub fn render_to(&self, out: &mut dyn Write, options: &RenderOptions) -> fmt::Result {
         match self {
-            ConflictRef::Commit(commit) => Display::fmt(commit, f),
-            ConflictRef::Branch(branch) => {
-                // use spare flag to show no remote info on conflict
-                if f.alternate() {
-                    write!(f, "{:#0}", branch)
-                } else {
-                    write!(f, "{:0}", branch)
-                }
-            }
+            ConflictRef::Commit(commit) => commit.render_to(out, options),
+            // no remote info on conflict
+            ConflictRef::Branch(branch) => branch.render_to(
+                out,
+                &RenderOptions {
+                    sparse: true,
+                    ..*options
+                },
+            ),
         }
     }
 }
 
+impl Display for ConflictRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let options = RenderOptions::from_formatter(f);
+        self.render_to(f, &options)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Tag(String);
 
 impl Tag {
@@ -105,29 +153,71 @@ impl Tag {
     }
 }
 
+impl Tag {
+    pub fn render_to(&self, out: &mut dyn Write, options: &RenderOptions) -> fmt::Result {
+        out.write_char('[')?;
+        options.paint(out, Role::Tag, &self.0)?;
+        out.write_char(']')
+    }
+}
+
 impl Display for Tag {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        use termion::{color, style};
-
-        if f.alternate() {
-            write!(
-                f,
-                "[{}{}{}{}]",
-                style::Bold,
-                color::Fg(color::Yellow),
-                self.0,
-                style::Reset
-            )
-        } else {
-            write!(f, "[{}]", self.0)
+        let options = RenderOptions::from_formatter(f);
+        self.render_to(f, &options)
+    }
+}
+
+/// A `git describe` style name, the closest tag reachable from a commit and how far behind the
+/// commit it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Describe {
+    tag: String,
+    offset: usize,
+    commit: Commit,
+}
+
+impl Describe {
+    pub fn new(tag: String, offset: usize, commit: Commit) -> Self {
+        Self {
+            tag,
+            offset,
+            commit,
+        }
+    }
+
+    pub fn with_link(self, link: Option<String>) -> Self {
+        Self {
+            commit: self.commit.with_link(link),
+            ..self
         }
     }
 }
 
+impl Describe {
+    pub fn render_to(&self, out: &mut dyn Write, options: &RenderOptions) -> fmt::Result {
+        // the hash length is meant for the hash only
+        options.paint(out, Role::Tag, &self.tag)?;
+        write!(out, "-{}-g", self.offset)?;
+
+        self.commit.render_to(out, options)
+    }
+}
+
+impl Display for Describe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let options = RenderOptions::from_formatter(f);
+        self.render_to(f, &options)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DetachedRef {
     Commit(Commit),
     Tag(Tag),
+    Describe(Describe),
 }
 
 impl DetachedRef {
@@ -138,64 +228,102 @@ impl DetachedRef {
     pub fn tag(tag: String) -> Self {
         Self::Tag(Tag::new(tag))
     }
+
+    pub fn describe(tag: String, offset: usize, hash: String) -> Self {
+        Self::Describe(Describe::new(tag, offset, Commit::new(hash)))
+    }
 }
 
-impl Display for DetachedRef {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl DetachedRef {
+    pub fn render_to(&self, out: &mut dyn Write, options: &RenderOptions) -> fmt::Result {
         match self {
-            DetachedRef::Commit(commit) => Display::fmt(commit, f),
-            DetachedRef::Tag(tag) => Display::fmt(tag, f),
+            DetachedRef::Commit(commit) => commit.render_to(out, options),
+            DetachedRef::Tag(tag) => tag.render_to(out, options),
+            DetachedRef::Describe(describe) => describe.render_to(out, options),
         }
     }
 }
 
+impl Display for DetachedRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let options = RenderOptions::from_formatter(f);
+        self.render_to(f, &options)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Prompt {
+    /// No commit checked out, `branch` is set on an unborn branch.
     Headless {
+        branch: Option<String>,
         working_tree: Changes,
         index: Changes,
+        submodules: Submodules,
         stash: usize,
     },
     Clean {
         head: Branch,
         stash: usize,
+        worktree: bool,
+        /// The first line of the HEAD commit message, see `head.subject`.
+        subject: Option<String>,
     },
     Detached {
         head: DetachedRef,
         working_tree: Changes,
         index: Changes,
+        submodules: Submodules,
         stash: usize,
+        worktree: bool,
     },
     Working {
         branch: Branch,
         working_tree: Changes,
         index: Changes,
+        submodules: Submodules,
         stash: usize,
+        worktree: bool,
+        subject: Option<String>,
     },
+    /// A merge, rebase, cherry-pick or revert in progress, stopped at conflicts or paused without
+    /// any.
     Conflicted {
         kind: ConflictKind,
         source: ConflictRef,
         target: ConflictRef,
         working_tree: Changes,
         index: Changes,
-        conflicts: usize,
+        submodules: Submodules,
+        conflicts: Conflicts,
         stash: usize,
+        worktree: bool,
     },
 }
 
 impl Prompt {
-    pub fn headless(working_tree: Changes, index: Changes, stash: usize) -> Self {
+    pub fn headless(
+        branch: Option<String>,
+        working_tree: Changes,
+        index: Changes,
+        submodules: Submodules,
+        stash: usize,
+    ) -> Self {
         Self::Headless {
+            branch,
             working_tree,
             index,
+            submodules,
             stash,
         }
     }
 
-    pub fn clean(branch: Branch, stash: usize) -> Self {
+    pub fn clean(branch: Branch, stash: usize, worktree: bool) -> Self {
         Self::Clean {
             head: branch,
             stash,
+            worktree,
+            subject: None,
         }
     }
 
@@ -203,33 +331,59 @@ impl Prompt {
         head: DetachedRef,
         working_tree: Changes,
         index: Changes,
+        submodules: Submodules,
         stash: usize,
+        worktree: bool,
     ) -> Self {
         Self::Detached {
             head,
             working_tree,
             index,
+            submodules,
             stash,
+            worktree,
         }
     }
 
-    pub fn working(branch: Branch, working_tree: Changes, index: Changes, stash: usize) -> Self {
+    pub fn working(
+        branch: Branch,
+        working_tree: Changes,
+        index: Changes,
+        submodules: Submodules,
+        stash: usize,
+        worktree: bool,
+    ) -> Self {
         Self::Working {
             branch,
             working_tree,
             index,
+            submodules,
             stash,
+            worktree,
+            subject: None,
         }
     }
 
+    /// Sets the subject of the HEAD commit on branches, other prompts don't show one.
+    pub fn with_subject(mut self, subject: Option<String>) -> Self {
+        if let Prompt::Clean { subject: s, .. } | Prompt::Working { subject: s, .. } = &mut self {
+            *s = subject;
+        }
+
+        self
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn conflict(
         kind: ConflictKind,
         source: ConflictRef,
         target: ConflictRef,
         working_tree: Changes,
         index: Changes,
-        conflicts: usize,
+        submodules: Submodules,
+        conflicts: Conflicts,
         stash: usize,
+        worktree: bool,
     ) -> Self {
         Self::Conflicted {
             kind,
@@ -237,154 +391,327 @@ impl Prompt {
             target,
             working_tree,
             index,
+            submodules,
             conflicts,
             stash,
+            worktree,
+        }
+    }
+
+    /// A plain name for what is checked out, the branch being worked on during a conflict.
+    pub fn head_name(&self) -> String {
+        match self {
+            Prompt::Headless {
+                branch: Some(branch),
+                ..
+            } => branch.clone(),
+            Prompt::Headless { branch: None, .. } => "(headless)".to_owned(),
+            Prompt::Clean { head: branch, .. } | Prompt::Working { branch, .. } => {
+                branch.local().to_owned()
+            }
+            Prompt::Detached { head, .. } => format!("{head:7}"),
+            Prompt::Conflicted {
+                kind: ConflictKind::Rebase,
+                target: head,
+                ..
+            }
+            | Prompt::Conflicted { source: head, .. } => format!("{head:07}"),
+        }
+    }
+
+    pub fn branch(&self) -> Option<&Branch> {
+        match self {
+            Prompt::Clean { head: branch, .. } | Prompt::Working { branch, .. } => Some(branch),
+            _ => None,
+        }
+    }
+
+    pub fn working_tree(&self) -> Option<&Changes> {
+        match self {
+            Prompt::Clean { .. } => None,
+            Prompt::Headless { working_tree, .. }
+            | Prompt::Detached { working_tree, .. }
+            | Prompt::Working { working_tree, .. }
+            | Prompt::Conflicted { working_tree, .. } => Some(working_tree),
+        }
+    }
+
+    pub fn index(&self) -> Option<&Changes> {
+        match self {
+            Prompt::Clean { .. } => None,
+            Prompt::Headless { index, .. }
+            | Prompt::Detached { index, .. }
+            | Prompt::Working { index, .. }
+            | Prompt::Conflicted { index, .. } => Some(index),
         }
     }
-}
 
-fn fmt_stash(f: &mut std::fmt::Formatter<'_>, stash: usize) -> std::fmt::Result {
-    use termion::{color, style};
-
-    if stash != 0 {
-        if f.alternate() {
-            write!(
-                f,
-                " :: {}s{}[{}]",
-                color::Fg(color::Magenta),
-                style::Reset,
-                stash
-            )?;
-        } else {
-            write!(f, " :: s[{}]", stash)?;
+    pub fn submodules(&self) -> Option<&Submodules> {
+        match self {
+            Prompt::Clean { .. } => None,
+            Prompt::Headless { submodules, .. }
+            | Prompt::Detached { submodules, .. }
+            | Prompt::Working { submodules, .. }
+            | Prompt::Conflicted { submodules, .. } => Some(submodules),
+        }
+    }
+
+    pub fn stash(&self) -> usize {
+        match self {
+            Prompt::Headless { stash, .. }
+            | Prompt::Clean { stash, .. }
+            | Prompt::Detached { stash, .. }
+            | Prompt::Working { stash, .. }
+            | Prompt::Conflicted { stash, .. } => *stash,
         }
     }
 
+    /// The operation in progress, if any.
+    pub fn operation(&self) -> Option<&ConflictKind> {
+        match self {
+            Prompt::Conflicted { kind, .. } => Some(kind),
+            _ => None,
+        }
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.working_tree().is_some_and(Changes::any)
+            || self.index().is_some_and(Changes::any)
+            || self.submodules().is_some_and(Submodules::any)
+    }
+
+    pub fn conflicts(&self) -> usize {
+        match self {
+            Prompt::Conflicted { conflicts, .. } => conflicts.total(),
+            _ => 0,
+        }
+    }
+}
+
+fn render_worktree(out: &mut dyn Write, options: &RenderOptions, worktree: bool) -> fmt::Result {
+    if worktree && !options.hidden.contains(Segment::Worktree) {
+        out.write_char(' ')?;
+        options.paint(out, Role::Worktree, options.glyph(Glyph::Worktree))?;
+    }
+
     Ok(())
 }
 
-fn fmt_changes(
-    f: &mut std::fmt::Formatter<'_>,
+fn render_stash(out: &mut dyn Write, options: &RenderOptions, stash: usize) -> fmt::Result {
+    if stash != 0 && !options.hidden.contains(Segment::Stash) {
+        options.separator(out)?;
+        out.write_char(' ')?;
+        options.paint(out, Role::Stash, options.glyph(Glyph::Stash))?;
+        write!(out, "[{stash}]")?;
+    }
+
+    Ok(())
+}
+
+/// Long enough to recognize a commit by, short enough to leave room for typing.
+pub const SUBJECT_LEN: usize = 32;
+
+fn render_subject(
+    out: &mut dyn Write,
+    options: &RenderOptions,
+    subject: Option<&str>,
+) -> fmt::Result {
+    let Some(subject) = subject.filter(|_| !options.hidden.contains(Segment::Subject)) else {
+        return Ok(());
+    };
+
+    let truncated = util::truncate(subject, SUBJECT_LEN, options.glyph(Glyph::Ellipsis));
+
+    out.write_char(' ')?;
+    options.paint(out, Role::Muted, format_args!("\"{truncated}\""))
+}
+
+fn render_changes(
+    out: &mut dyn Write,
+    options: &RenderOptions,
     working_tree: &Changes,
     index: &Changes,
-    conflicts: usize,
-) -> std::fmt::Result {
-    use termion::{color, style};
-
-    if working_tree.any() || index.any() || conflicts != 0 {
-        f.write_str(" ::")?;
-    }
-
-    if conflicts != 0 {
-        if f.alternate() {
-            write!(
-                f,
-                " [{}{}!{conflicts}{}]",
-                style::Bold,
-                color::Fg(color::Red),
-                style::Reset
-            )?;
-        } else {
-            write!(f, " [!{conflicts}]")?;
-        }
+    submodules: &Submodules,
+    conflicts: &Conflicts,
+) -> fmt::Result {
+    // hidden parts count as none at all
+    let shown = |segment, any| any && !options.hidden.contains(segment);
+    let working_tree =
+        shown(Segment::WorkingTree, working_tree.any_shown(options)).then_some(working_tree);
+    let index = shown(Segment::Index, index.any_shown(options)).then_some(index);
+    let submodules = shown(Segment::Submodules, submodules.any()).then_some(submodules);
+    let conflicts = shown(Segment::Conflicts, conflicts.any()).then_some(conflicts);
+
+    if working_tree.is_some() || index.is_some() || submodules.is_some() || conflicts.is_some() {
+        options.separator(out)?;
+    }
+
+    if let Some(conflicts) = conflicts {
+        out.write_str(" [")?;
+        conflicts.render_to(out, options)?;
+        out.write_char(']')?;
+    }
+
+    if let Some(working_tree) = working_tree {
+        out.write_char(' ')?;
+        options.paint(out, Role::WorkingTree, options.glyph(Glyph::WorkingTree))?;
+        out.write_char('[')?;
+        working_tree.render_to(out, options)?;
+        out.write_char(']')?;
     }
 
-    if working_tree.any() {
-        write!(f, " {}w{}[", color::Fg(color::Yellow), style::Reset)?;
-        Display::fmt(working_tree, f)?;
-        f.write_char(']')?;
+    if let Some(index) = index {
+        out.write_char(' ')?;
+        options.paint(out, Role::Index, options.glyph(Glyph::Index))?;
+        out.write_char('[')?;
+        index.render_to(out, options)?;
+        out.write_char(']')?;
     }
 
-    if index.any() {
-        write!(f, " {}i{}[", color::Fg(color::Green), style::Reset)?;
-        Display::fmt(index, f)?;
-        f.write_char(']')?;
+    if let Some(submodules) = submodules {
+        out.write_char(' ')?;
+        options.paint(out, Role::Submodules, "sub")?;
+        out.write_char('[')?;
+        submodules.render_to(out, options)?;
+        out.write_char(']')?;
     }
 
     Ok(())
 }
 
-impl Display for Prompt {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        use termion::{color, style};
+impl Prompt {
+    /// The prompt as a string, `Display` renders the same with options taken from the formatter.
+    pub fn render(&self, options: &RenderOptions) -> String {
+        let mut out = String::new();
+        self.render_to(&mut out, options)
+            .expect("writing to a String does not fail");
+        out
+    }
 
+    // whatever HEAD is, for branches only the local name
+    fn render_head(&self, out: &mut dyn Write, options: &RenderOptions) -> fmt::Result {
         match self {
             Prompt::Headless {
-                working_tree,
-                index,
-                stash,
+                branch: Some(branch),
+                ..
             } => {
-                if f.alternate() {
-                    write!(
-                        f,
-                        "[{}{}headless{}]",
-                        style::Bold,
-                        color::Fg(color::Blue),
-                        style::Reset
-                    )?;
-                } else {
-                    write!(f, "[headless]")?;
+                options.paint(out, Role::BranchName, branch)?;
+                out.write_char(' ')?;
+                options.paint(out, Role::Muted, "(no commits)")
+            }
+            Prompt::Headless { branch: None, .. } => {
+                out.write_char('[')?;
+                options.paint(out, Role::Headless, "headless")?;
+                out.write_char(']')
+            }
+            Prompt::Clean { head: branch, .. } | Prompt::Working { branch, .. } => {
+                branch.render_segment(Segment::Branch, out, options)
+            }
+            Prompt::Detached { head, .. } => head.render_to(
+                out,
+                &RenderOptions {
+                    hash_len: options.hash_len.or(Some(7)),
+                    ..*options
+                },
+            ),
+            Prompt::Conflicted {
+                kind,
+                source,
+                target,
+                ..
+            } => match kind {
+                // a picked or reverted commit is brought in like a merged one
+                ConflictKind::Merge | ConflictKind::CherryPick | ConflictKind::Revert => {
+                    source.render_to(out, options)?;
+                    options.text(out, &format!(" {} ", options.glyph(Glyph::Merge)))?;
+                    target.render_to(out, options)
                 }
+                ConflictKind::Other => source.render_to(out, options),
+                ConflictKind::Rebase => {
+                    target.render_to(out, options)?;
+                    options.text(out, &format!(" {} ", options.glyph(Glyph::Rebase)))?;
+                    source.render_to(out, options)
+                }
+            },
+        }
+    }
 
-                fmt_stash(f, *stash)?;
-                fmt_changes(f, &working_tree, &index, 0)?;
+    pub fn render_to(&self, out: &mut dyn Write, options: &RenderOptions) -> fmt::Result {
+        self.render_left(out, options)?;
+        self.render_right(out, options)
+    }
+
+    /// Whatever HEAD is with its upstream and whether this is a linked worktree, the start of
+    /// [`render_to`](Self::render_to).
+    pub fn render_left(&self, out: &mut dyn Write, options: &RenderOptions) -> fmt::Result {
+        match self {
+            Prompt::Headless { .. } => self.render_head(out, options),
+            Prompt::Clean {
+                head: branch,
+                worktree,
+                ..
+            }
+            | Prompt::Working {
+                branch, worktree, ..
+            } => {
+                branch.render_to(out, options)?;
+                render_worktree(out, options, *worktree)
             }
-            Prompt::Clean { head, stash } => {
-                Display::fmt(head, f)?;
-                fmt_stash(f, *stash)?;
+            Prompt::Detached { worktree, .. } | Prompt::Conflicted { worktree, .. } => {
+                self.render_head(out, options)?;
+                render_worktree(out, options, *worktree)
             }
-            Prompt::Detached {
-                head,
+        }
+    }
+
+    /// The stash, changes and subject, the rest of [`render_to`](Self::render_to) after
+    /// [`render_left`](Self::render_left), led by its separator.
+    pub fn render_right(&self, out: &mut dyn Write, options: &RenderOptions) -> fmt::Result {
+        let none = Conflicts::new();
+        let (changes, subject) = match self {
+            Prompt::Headless {
                 working_tree,
                 index,
-                stash,
-            } => {
-                if f.alternate() {
-                    write!(f, "{head:#7}")?;
-                } else {
-                    write!(f, "{head:7}")?;
-                }
-
-                fmt_stash(f, *stash)?;
-                fmt_changes(f, &working_tree, &index, 0)?;
+                submodules,
+                ..
             }
+            | Prompt::Detached {
+                working_tree,
+                index,
+                submodules,
+                ..
+            } => (Some((working_tree, index, submodules, &none)), None),
+            Prompt::Clean { subject, .. } => (None, subject.as_deref()),
             Prompt::Working {
-                branch,
                 working_tree,
                 index,
-                stash,
-            } => {
-                Display::fmt(branch, f)?;
-                fmt_stash(f, *stash)?;
-                fmt_changes(f, &working_tree, &index, 0)?;
-            }
+                submodules,
+                subject,
+                ..
+            } => (
+                Some((working_tree, index, submodules, &none)),
+                subject.as_deref(),
+            ),
             Prompt::Conflicted {
-                kind,
-                source,
-                target,
                 working_tree,
                 index,
+                submodules,
                 conflicts,
-                stash,
-            } => {
-                match kind {
-                    ConflictKind::Merge => {
-                        Display::fmt(source, f)?;
-                        f.write_str(" <- ")?;
-                        Display::fmt(target, f)?;
-                    }
-                    ConflictKind::Rebase => {
-                        Display::fmt(target, f)?;
-                        f.write_str(" -> ")?;
-                        Display::fmt(source, f)?;
-                    }
-                }
+                ..
+            } => (Some((working_tree, index, submodules, conflicts)), None),
+        };
 
-                fmt_stash(f, *stash)?;
-                fmt_changes(f, &working_tree, &index, *conflicts)?;
-            }
+        render_stash(out, options, self.stash())?;
+        if let Some((working_tree, index, submodules, conflicts)) = changes {
+            render_changes(out, options, working_tree, index, submodules, conflicts)?;
         }
+        render_subject(out, options, subject)
+    }
+}
 
-        Ok(())
+impl Display for Prompt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let options = RenderOptions::from_formatter(f);
+        self.render_to(f, &options)
     }
 }
@@ -0,0 +1,324 @@
+use std::fmt::{self, Display, Formatter, Write};
+
+use super::{Background, Color, Glyph, Overrides, Role, Segments, Style, Theme};
+
+/// How [`RepoSnapshot::hint`](super::RepoSnapshot::hint) is shown, see `hint.style`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HintStyle {
+    /// `⇣`, `⇡` or `⛔`.
+    Glyph,
+    /// `needs pull`, `needs push` or `push blocked: protected`.
+    Text,
+}
+
+impl HintStyle {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "glyph" => Some(Self::Glyph),
+            "text" => Some(Self::Text),
+            _ => None,
+        }
+    }
+}
+
+/// Where branch names cut to [`RenderOptions::branch_len`] lose their characters, see
+/// `branch.ellipsis`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Truncation {
+    /// `feature/JIRA-12345-i…`
+    #[default]
+    End,
+    /// `feature/JI…-the-thing`, keeping the prefix and the end, which tell branches apart best.
+    Middle,
+}
+
+impl Truncation {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "end" => Some(Self::End),
+            "middle" => Some(Self::Middle),
+            _ => None,
+        }
+    }
+}
+
+/// How styles are written, see [`RenderOptions::paint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Markup {
+    /// ANSI escapes for terminals.
+    #[default]
+    Ansi,
+    /// `#[fg=yellow,bold]` directives for tmux's `status-left` and `status-right`, `#` escaped.
+    Tmux,
+    /// `<span class="epg-<role>">` elements styled by `output::stylesheet`, text escaped.
+    Html,
+    /// zsh's own `%F{3}%B` prompt escapes, `%` escaped, which zsh measures without `%{ %}`.
+    Zsh,
+}
+
+/// How [`Prompt::render`](super::Prompt::render) renders a prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RenderOptions<'a> {
+    /// Color segments using `markup`.
+    pub color: bool,
+    pub markup: Markup,
+    pub theme: Theme,
+    /// What the theme's colors are tuned for, configured colors are taken as they are.
+    pub background: Background,
+    /// Nothing but ASCII, glyphs spelled out in ASCII whatever the theme and any other char in
+    /// text from outside replaced by `?`, e.g. for serial consoles.
+    pub ascii: bool,
+    /// What the user configured in place of the theme, shared by all copies of the options.
+    pub overrides: Option<&'a Overrides>,
+    /// Only the local name of branches, without upstream, divergence or base.
+    pub sparse: bool,
+    /// Only the total of each set of changes, e.g. `w[4]` rather than `w[~1%3]`.
+    pub totals: bool,
+    /// Truncate branch names, local and upstream, to this many characters, the mark that they were
+    /// cut included.
+    pub branch_len: Option<usize>,
+    pub truncation: Truncation,
+    /// Truncate commit hashes to this many characters.
+    pub hash_len: Option<usize>,
+    /// Break conflicts down by kind, e.g. `[!3: uu2 dd1]`.
+    pub detailed_conflicts: bool,
+    /// Always spell out upstream branch names rather than collapsing those matching the local
+    /// name to `~`.
+    pub full_upstream: bool,
+    /// Leave out the upstream of the same name while level with it, `[origin/~][]` tells nothing.
+    pub compact: bool,
+    /// Show what to do about the divergence from the upstream, not at all if `None`.
+    pub hints: Option<HintStyle>,
+    /// Parts left out of the prompt, e.g. by `--no-stash`.
+    pub hidden: Segments,
+}
+
+impl RenderOptions<'_> {
+    /// The options `Display` implementations derive from their formatter, `#` for color, `0` for
+    /// sparse and the width for the hash length.
+    pub fn from_formatter(f: &Formatter<'_>) -> Self {
+        Self {
+            color: f.alternate(),
+            markup: Markup::Ansi,
+            theme: Theme::Default,
+            background: Background::Dark,
+            ascii: false,
+            overrides: None,
+            sparse: f.sign_aware_zero_pad(),
+            totals: false,
+            branch_len: None,
+            truncation: Truncation::End,
+            hash_len: f.width(),
+            detailed_conflicts: false,
+            full_upstream: false,
+            compact: false,
+            hints: None,
+            hidden: Segments::default(),
+        }
+    }
+}
+
+// the 256 color form of the 16 colors, like termion writes them, and 24-bit colors
+fn fg(out: &mut dyn Write, color: Color) -> fmt::Result {
+    match color.index() {
+        Some(index) => write!(out, "\x1b[38;5;{index}m"),
+        None => {
+            let (r, g, b) = color.rgb();
+            write!(out, "\x1b[38;2;{r};{g};{b}m")
+        }
+    }
+}
+
+fn tmux_color(color: Color) -> String {
+    let name = match color {
+        Color::Black => "black",
+        Color::Red => "red",
+        Color::Green => "green",
+        Color::Yellow => "yellow",
+        Color::Blue => "blue",
+        Color::Magenta => "magenta",
+        Color::Cyan => "cyan",
+        Color::White => "white",
+        Color::LightBlack => "brightblack",
+        Color::LightRed => "brightred",
+        Color::LightGreen => "brightgreen",
+        Color::LightYellow => "brightyellow",
+        Color::LightBlue => "brightblue",
+        Color::LightMagenta => "brightmagenta",
+        Color::LightCyan => "brightcyan",
+        Color::LightWhite => "brightwhite",
+        Color::Indexed(index) => return format!("colour{index}"),
+        Color::Rgb(r, g, b) => return format!("#{r:02x}{g:02x}{b:02x}"),
+    };
+    name.to_owned()
+}
+
+// `%F{208}` or `%F{#ff8700}`, which zsh maps to the nearest palette color where needed
+fn zsh_color(color: Color) -> String {
+    match color.index() {
+        Some(index) => index.to_string(),
+        None => {
+            let (r, g, b) = color.rgb();
+            format!("#{r:02x}{g:02x}{b:02x}")
+        }
+    }
+}
+
+// text from outside, e.g. commit subjects or what `ci.command` printed, must not smuggle escapes
+// or line breaks into the prompt, nor directives into tmux or tags into html
+struct Printable<'w>(&'w mut dyn Write, &'w RenderOptions<'w>);
+
+impl Write for Printable<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let (markup, ascii) = (self.1.markup, self.1.ascii);
+        let special = |c: char| match markup {
+            Markup::Ansi => false,
+            Markup::Tmux => c == '#',
+            Markup::Html => matches!(c, '<' | '>' | '&' | '"'),
+            Markup::Zsh => c == '%',
+        };
+
+        if !s.contains(|c: char| c.is_control() || special(c) || (ascii && !c.is_ascii())) {
+            return self.0.write_str(s);
+        }
+
+        for c in s.chars() {
+            match (markup, c) {
+                (_, c) if ascii && (c.is_control() || !c.is_ascii()) => self.0.write_char('?')?,
+                (Markup::Tmux, '#') => self.0.write_str("##")?,
+                (Markup::Html, '<') => self.0.write_str("&lt;")?,
+                (Markup::Html, '>') => self.0.write_str("&gt;")?,
+                (Markup::Html, '&') => self.0.write_str("&amp;")?,
+                (Markup::Html, '"') => self.0.write_str("&quot;")?,
+                (Markup::Zsh, '%') => self.0.write_str("%%")?,
+                (_, c) if c.is_control() => self.0.write_char('\u{fffd}')?,
+                (_, c) => self.0.write_char(c)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> RenderOptions<'a> {
+    /// Writes `text` in the style of `role`, as is without color, control characters in `text`
+    /// are replaced.
+    pub fn paint(&self, out: &mut dyn Write, role: Role, text: impl Display) -> fmt::Result {
+        let style = self.style(role);
+        // classes are written even for plain roles, stylesheets may style any of them
+        if !self.color || (style.is_plain() && self.markup != Markup::Html) {
+            return write!(Printable(out, self), "{text}");
+        }
+
+        match self.markup {
+            Markup::Ansi => {
+                if style.bold {
+                    out.write_str("\x1b[1m")?;
+                }
+
+                if let Some(color) = style.fg {
+                    fg(out, color)?;
+                }
+            }
+            Markup::Tmux => {
+                let fg = style.fg.map(|color| format!("fg={}", tmux_color(color)));
+                let bold = style.bold.then(|| "bold".to_owned());
+                let attributes: Vec<String> = fg.into_iter().chain(bold).collect();
+                write!(out, "#[{}]", attributes.join(","))?;
+            }
+            Markup::Html => write!(out, "<span class=\"epg-{}\">", role.name())?,
+            Markup::Zsh => {
+                if style.bold {
+                    out.write_str("%B")?;
+                }
+
+                if let Some(color) = style.fg {
+                    write!(out, "%F{{{}}}", zsh_color(color))?;
+                }
+            }
+        }
+
+        write!(Printable(out, self), "{text}")?;
+        match self.markup {
+            Markup::Ansi => out.write_str("\x1b[m"),
+            Markup::Tmux => out.write_str("#[default]"),
+            Markup::Html => out.write_str("</span>"),
+            Markup::Zsh => {
+                if style.fg.is_some() {
+                    out.write_str("%f")?;
+                }
+
+                if style.bold {
+                    out.write_str("%b")?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    /// Like [`paint`](Self::paint), linked to `url` with OSC 8 where terminal escapes may be
+    /// written.
+    pub fn paint_link(
+        &self,
+        out: &mut dyn Write,
+        role: Role,
+        text: impl Display,
+        url: Option<&str>,
+    ) -> fmt::Result {
+        match url.filter(|_| self.is_terminal()) {
+            Some(url) => {
+                write!(out, "\x1b]8;;{url}\x1b\\")?;
+                self.paint(out, role, text)?;
+                out.write_str("\x1b]8;;\x1b\\")
+            }
+            None => self.paint(out, role, text),
+        }
+    }
+
+    /// Writes unstyled `text` which may mean something to the markup, e.g. the `<-` of a merge.
+    pub fn text(&self, out: &mut dyn Write, text: &str) -> fmt::Result {
+        Printable(out, self).write_str(text)
+    }
+
+    /// Writes the separator in front of a part of the prompt, ` ::` unless overridden, nothing if
+    /// it was set to be empty.
+    pub fn separator(&self, out: &mut dyn Write) -> fmt::Result {
+        match self
+            .overrides
+            .and_then(Overrides::separator)
+            .unwrap_or("::")
+        {
+            "" => Ok(()),
+            separator => {
+                out.write_char(' ')?;
+                self.text(out, separator)
+            }
+        }
+    }
+
+    /// How `role` looks with these options.
+    pub fn style(&self, role: Role) -> Style {
+        self.overrides
+            .and_then(|overrides| overrides.style(role))
+            .unwrap_or_else(|| {
+                let style = self.theme.style(role);
+                Style {
+                    fg: style.fg.map(|color| self.background.adapt(color)),
+                    ..style
+                }
+            })
+    }
+
+    /// How `glyph` is drawn with these options.
+    pub fn glyph(&self, glyph: Glyph) -> &'a str {
+        self.overrides
+            .and_then(|overrides| overrides.glyph(glyph))
+            .unwrap_or_else(|| glyph.text(self.theme, self.ascii))
+    }
+
+    /// Whether terminal-only escapes, e.g. OSC 8 links, may be written.
+    pub fn is_terminal(&self) -> bool {
+        self.color && self.markup == Markup::Ansi
+    }
+}
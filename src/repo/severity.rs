@@ -0,0 +1,89 @@
+use std::fmt::{self, Display, Write};
+
+use super::{Divergence, Glyph, Prompt, RenderOptions, Role};
+
+/// How much a repo state needs attention, ordered from least to most.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Severity {
+    Clean,
+    Ahead,
+    Dirty,
+    Behind,
+    Conflict,
+}
+
+impl Severity {
+    pub fn of(prompt: &Prompt) -> Self {
+        if prompt.conflicts() != 0 {
+            return Self::Conflict;
+        }
+
+        let (ahead, behind) = prompt
+            .branch()
+            .and_then(|branch| branch.divergence())
+            .map(Divergence::ahead_behind)
+            .unwrap_or_default();
+
+        if behind != 0 {
+            Self::Behind
+        } else if prompt.is_dirty() || prompt.operation().is_some() {
+            Self::Dirty
+        } else if ahead != 0 {
+            Self::Ahead
+        } else {
+            Self::Clean
+        }
+    }
+}
+
+impl Severity {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "clean" => Some(Severity::Clean),
+            "ahead" => Some(Severity::Ahead),
+            "dirty" => Some(Severity::Dirty),
+            "behind" => Some(Severity::Behind),
+            "conflict" => Some(Severity::Conflict),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Clean => "clean",
+            Severity::Ahead => "ahead",
+            Severity::Dirty => "dirty",
+            Severity::Behind => "behind",
+            Severity::Conflict => "conflict",
+        }
+    }
+}
+
+impl Severity {
+    /// The single symbol of `--output=symbol`.
+    pub fn render_to(self, out: &mut dyn Write, options: &RenderOptions) -> fmt::Result {
+        let role = match self {
+            Severity::Clean => Role::Success,
+            Severity::Ahead => Role::Ahead,
+            Severity::Dirty => Role::Warning,
+            Severity::Behind | Severity::Conflict => Role::Error,
+        };
+
+        options.paint(out, role, options.glyph(Glyph::Severity(self)))
+    }
+
+    pub fn render(self, options: &RenderOptions) -> String {
+        let mut out = String::new();
+        self.render_to(&mut out, options)
+            .expect("writing to a String does not fail");
+        out
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let options = RenderOptions::from_formatter(f);
+        self.render_to(f, &options)
+    }
+}
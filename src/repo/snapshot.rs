@@ -0,0 +1,466 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use crate::{forge::Forge, ticket::Ticket, util};
+
+use super::{
+    Branch, Changes, Commit, ConflictKind, ConflictRef, Conflicts, Describe, DetachedRef,
+    Divergence, Glyph, HintStyle, Prompt, RemoteBranch, RenderOptions, Role, Segment, Submodules,
+    Tag, Template, SUBJECT_LEN,
+};
+
+/// A merge, rebase, cherry-pick or revert in progress, or conflicts left behind without one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Operation {
+    pub kind: ConflictKind,
+    /// What is merged or rebased onto.
+    pub source: ConflictRef,
+    /// The branch being worked on.
+    pub target: ConflictRef,
+}
+
+/// A ref other than the upstream HEAD is compared against, see `branch.push` and
+/// `branch.fallback`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Comparison {
+    /// `<remote>/<branch>` or a local branch.
+    pub name: String,
+    pub ahead: usize,
+    pub behind: usize,
+}
+
+impl Comparison {
+    fn divergence(&self) -> Option<Divergence> {
+        (self.ahead + self.behind != 0).then(|| Divergence::new(self.ahead, self.behind))
+    }
+}
+
+/// What to do about the divergence from the upstream, see `hint.style`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Hint {
+    /// Behind, whether or not also ahead.
+    Pull,
+    Push,
+    /// Ahead on a branch listed in `check.protected`.
+    Blocked,
+}
+
+/// Lines changed according to `git diff --shortstat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiffStat {
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+/// Everything measured about a repo, without any decisions about how to present it.
+///
+/// Optional measurements are `None` if they were disabled or do not apply.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RepoSnapshot {
+    /// Unix seconds at which the snapshot was taken.
+    pub measured_at: u64,
+    /// `None` if there is no commit yet.
+    pub commit: Option<String>,
+    /// `None` if detached.
+    pub branch: Option<String>,
+    /// How long git abbreviates the detached commit, see `hash.abbrev`.
+    pub abbrev: Option<usize>,
+    /// The issue tracker id in the branch name, see `ticket.enabled`.
+    pub ticket: Option<Ticket>,
+    /// What `ci.command` printed for the branch.
+    pub ci: Option<String>,
+    /// What `pr.command` printed for the branch, nothing if there is no open pull request.
+    pub pr: Option<String>,
+    /// `<remote>/<branch>` if configured.
+    pub upstream: Option<String>,
+    /// The web UI of the upstream remote, or of `origin` without one, see `links.enabled`.
+    pub forge: Option<Forge>,
+    /// The upstream is configured but no longer exists.
+    pub upstream_gone: bool,
+    /// The branch is listed in `check.protected`.
+    pub protected: bool,
+    pub ahead: usize,
+    pub behind: usize,
+    /// Seconds since the last fetch if that is longer ago than `fetch.stale`, the divergence may
+    /// well be outdated then.
+    pub fetched: Option<u64>,
+    /// Where `git push` pushes to if that is not the upstream, see `branch.push`.
+    pub push: Option<Comparison>,
+    /// The base branch if there is no upstream, see `branch.fallback`.
+    pub fallback: Option<Comparison>,
+    /// The highest version tag pointing at HEAD, always looked up when detached, on branches see
+    /// `branch.tag`.
+    pub tag: Option<String>,
+    /// The closest tag reachable from a detached head, see `detached.describe`.
+    pub describe: Option<Describe>,
+    /// A linked worktree rather than the main one.
+    pub worktree: bool,
+    pub operation: Option<Operation>,
+    pub working_tree: Changes,
+    pub index: Changes,
+    /// Files hidden from status by skip-worktree or assume-unchanged, see `status.hidden`.
+    pub hidden: Option<usize>,
+    /// Lines changed in the working tree and index, see `diff.stat`.
+    pub unstaged_lines: Option<DiffStat>,
+    pub staged_lines: Option<DiffStat>,
+    pub submodules: Submodules,
+    pub conflicts: Conflicts,
+    pub stash: usize,
+    /// The subject of `stash@{0}` and the branch it was made on, see `stash.subject`.
+    pub stash_subject: Option<(String, String)>,
+    /// See `status.ignored`.
+    pub ignored: Option<usize>,
+    /// Seconds since the last checkout if that was within `head.switched`.
+    pub switched: Option<u64>,
+    /// The first line of the HEAD commit message, see `head.subject`.
+    pub subject: Option<String>,
+    /// The `%G?` signature status of HEAD, see `head.signature`.
+    pub signature: Option<char>,
+    /// Seconds since HEAD was committed, see `head.age`.
+    pub head_age: Option<u64>,
+    /// Seconds since the branch diverged from the base branch, see `branch.age`.
+    pub age: Option<u64>,
+    /// Commits not on the base branch, see `branch.commits`.
+    pub commits: Option<usize>,
+    /// Branches with commits on top of HEAD, see `stack.enabled`.
+    pub stacked: Option<usize>,
+    /// Porcelain headers this release does not understand, by key, e.g. a future
+    /// `# branch.foo <value>`.
+    pub headers: BTreeMap<String, String>,
+    /// Measured from inside the git dir rather than a work tree, nothing else is measured then.
+    pub inside_git_dir: bool,
+    /// Untracked files, the diffstat and `describe` were skipped because status was slow before,
+    /// see `adaptive`.
+    pub reduced: bool,
+    /// Why the snapshot may be partial, e.g. a timed out `ci.command` or status lines this
+    /// release does not understand.
+    pub warnings: Vec<String>,
+}
+
+// well known states get a symbol, anything else is shown as is
+fn render_ci(out: &mut String, options: &RenderOptions, ci: &str) {
+    let (symbol, role) = match ci.to_ascii_lowercase().as_str() {
+        "success" | "passed" | "pass" | "ok" => (options.glyph(Glyph::CiSuccess), Role::Success),
+        "failure" | "failed" | "fail" | "error" | "cancelled" => {
+            (options.glyph(Glyph::CiFailure), Role::Error)
+        }
+        "pending" | "queued" | "in_progress" | "running" | "waiting" => {
+            (options.glyph(Glyph::CiPending), Role::Warning)
+        }
+        _ => (ci, Role::Muted),
+    };
+
+    out.push_str(" ci:");
+    let _ = options.paint(out, role, symbol);
+}
+
+// good signatures get a closed lock, anything else is a problem for teams enforcing them
+fn render_signature(out: &mut String, options: &RenderOptions, signature: char) {
+    let (glyph, role) = match signature {
+        // `U` is a good signature of a key without known validity
+        'G' | 'U' => (Glyph::Signed, Role::Success),
+        // good, but expired
+        'X' | 'Y' => (Glyph::Signed, Role::Warning),
+        'N' => (Glyph::Unsigned, Role::Error),
+        // bad, revoked or not checkable
+        _ => (Glyph::Signed, Role::Error),
+    };
+    let symbol = options.glyph(glyph);
+
+    if options.color {
+        out.push(' ');
+        let _ = options.paint(out, role, symbol);
+    } else if matches!(signature, 'G' | 'U' | 'N') {
+        let _ = write!(out, " {symbol}");
+    } else {
+        // without color a problem needs its own marker
+        let _ = write!(out, " {symbol}!");
+    }
+}
+
+fn render_hint(out: &mut String, options: &RenderOptions, style: HintStyle, hint: Hint) {
+    let (glyph, text, role) = match hint {
+        Hint::Pull => (Glyph::HintPull, "needs pull", Role::Warning),
+        Hint::Push => (Glyph::HintPush, "needs push", Role::Ahead),
+        Hint::Blocked => (Glyph::HintBlocked, "push blocked: protected", Role::Error),
+    };
+
+    out.push(' ');
+    let _ = match style {
+        HintStyle::Glyph => options.paint(out, role, options.glyph(glyph)),
+        HintStyle::Text => options.paint(out, role, text),
+    };
+}
+
+impl RepoSnapshot {
+    /// What to do about the divergence from the upstream, pulling first as a push would be
+    /// rejected while behind.
+    pub fn hint(&self) -> Option<Hint> {
+        if self.branch.is_none() || self.upstream.is_none() || self.upstream_gone {
+            return None;
+        }
+
+        match (self.ahead, self.behind) {
+            (_, 1..) => Some(Hint::Pull),
+            (1.., 0) if self.protected => Some(Hint::Blocked),
+            (1.., 0) => Some(Hint::Push),
+            (0, 0) => None,
+        }
+    }
+
+    /// The prompt followed by whatever else was measured, in this order: the hint, ignored
+    /// files, a stale fetch, CI, the pull request, hidden files, changed lines, the latest stash,
+    /// a recent switch, the signature and age of HEAD and the reduced marker.
+    ///
+    /// Only the prompt itself can be laid out, see [`Template::from_order`].
+    pub fn render(&self, options: &RenderOptions) -> String {
+        self.render_with(options, None)
+    }
+
+    /// Like [`render`](Self::render) with the prompt itself laid out by `template` if set.
+    pub fn render_with(&self, options: &RenderOptions, template: Option<&Template>) -> String {
+        if self.inside_git_dir {
+            let mut out = String::from("[");
+            let _ = options.paint(&mut out, Role::GitDir, ".git");
+            out.push(']');
+            return out;
+        }
+
+        let prompt = self.prompt();
+        let mut out = match template {
+            Some(template) => prompt.render_template(template, options),
+            None => prompt.render(options),
+        };
+
+        self.render_extras(&mut out, options);
+        out
+    }
+
+    /// Like [`render`](Self::render) cut in two for prompts on both sides of the line, e.g. zsh's
+    /// `PROMPT` and `RPROMPT`: the head and upstream on the left, anything else on the right.
+    pub fn render_split(&self, options: &RenderOptions) -> (String, String) {
+        if self.inside_git_dir {
+            return (self.render(options), String::new());
+        }
+
+        let prompt = self.prompt();
+        let mut left = String::new();
+        let mut right = String::new();
+        let _ = prompt.render_left(&mut left, options);
+        let _ = prompt.render_right(&mut right, options);
+        self.render_extras(&mut right, options);
+
+        // the separators lead each part, the right side starts on its own
+        let mut separator = String::new();
+        let _ = options.separator(&mut separator);
+        let right = right.strip_prefix(&separator).unwrap_or(&right);
+        (left, right.strip_prefix(' ').unwrap_or(right).to_owned())
+    }
+
+    // whatever follows the prompt itself, each led by its separator
+    fn render_extras(&self, out: &mut String, options: &RenderOptions) {
+        if let Some((style, hint)) = options.hints.zip(self.hint()) {
+            render_hint(out, options, style, hint);
+        }
+
+        if let Some(ignored) = self.ignored.filter(|&ignored| ignored != 0) {
+            let _ = options.separator(out);
+            out.push(' ');
+            let _ = options.paint(out, Role::Muted, "ig");
+            let _ = write!(out, "[{ignored}]");
+        }
+
+        if let Some(fetched) = self.fetched {
+            out.push(' ');
+            let _ = options.paint(
+                out,
+                Role::Warning,
+                format_args!("{}{}", options.glyph(Glyph::Stale), util::fmt_age(fetched)),
+            );
+        }
+
+        if let Some(ci) = &self.ci {
+            render_ci(out, options, ci);
+        }
+
+        // a bare number is a pull request number
+        if let Some(pr) = &self.pr {
+            let hash = if pr.bytes().all(|b| b.is_ascii_digit()) {
+                "#"
+            } else {
+                ":"
+            };
+
+            out.push(' ');
+            let _ = options.paint(out, Role::PullRequest, format_args!("PR{hash}{pr}"));
+        }
+
+        if let Some(hidden) = self.hidden.filter(|&hidden| hidden != 0) {
+            out.push(' ');
+            let glyph = options.glyph(Glyph::Hidden);
+            let _ = options.paint(out, Role::Warning, format_args!("{glyph}{hidden}"));
+        }
+
+        // hidden like the changes they count
+        let shown = |segment| !options.hidden.contains(segment);
+        let lines = [
+            self.unstaged_lines.filter(|_| shown(Segment::WorkingTree)),
+            self.staged_lines.filter(|_| shown(Segment::Index)),
+        ];
+        let insertions: usize = lines.iter().flatten().map(|stat| stat.insertions).sum();
+        let deletions: usize = lines.iter().flatten().map(|stat| stat.deletions).sum();
+
+        if insertions + deletions != 0 {
+            out.push(' ');
+            let _ = options.paint(out, Role::Added, format_args!("+{insertions}"));
+            out.push(' ');
+            let minus = options.glyph(Glyph::Minus);
+            let _ = options.paint(out, Role::Removed, format_args!("{minus}{deletions}"));
+        }
+
+        let stash_subject = self
+            .stash_subject
+            .as_ref()
+            .filter(|_| shown(Segment::Stash));
+        if let Some((branch, subject)) = stash_subject {
+            let subject = util::truncate(subject, SUBJECT_LEN, options.glyph(Glyph::Ellipsis));
+            let elsewhere = (self.branch.as_ref() != Some(branch)).then_some(branch);
+
+            let from = elsewhere.map_or_else(String::new, |branch| format!(" from {branch}"));
+
+            out.push(' ');
+            let glyph = options.glyph(Glyph::Stash);
+            let _ = options.paint(out, Role::Stash, format_args!("{glyph}0"));
+            out.push(' ');
+            let _ = options.paint(out, Role::Muted, format_args!("\"{subject}\"{from}"));
+        }
+
+        if self.switched.is_some() {
+            out.push(' ');
+            let _ = options.paint(out, Role::Warning, options.glyph(Glyph::Switched));
+        }
+
+        if let Some(signature) = self.signature {
+            render_signature(out, options, signature);
+        }
+
+        if let Some(age) = self.head_age {
+            out.push(' ');
+            let _ = options.paint(out, Role::Muted, util::fmt_age(age));
+        }
+
+        if self.reduced {
+            out.push(' ');
+            let _ = options.paint(out, Role::Muted, options.glyph(Glyph::Reduced));
+        }
+    }
+
+    pub fn prompt(&self) -> Prompt {
+        let working_tree = self.working_tree.clone();
+        let index = self.index.clone();
+
+        let Some(commit) = &self.commit else {
+            return Prompt::headless(
+                self.branch.clone(),
+                working_tree,
+                index,
+                self.submodules,
+                self.stash,
+            );
+        };
+
+        if let Some(operation) = &self.operation {
+            return Prompt::conflict(
+                operation.kind.clone(),
+                operation.source.clone(),
+                operation.target.clone(),
+                working_tree,
+                index,
+                self.submodules,
+                self.conflicts,
+                self.stash,
+                self.worktree,
+            );
+        }
+
+        let Some(local) = &self.branch else {
+            let link = |hash: &str| self.forge.as_ref().map(|forge| forge.commit_url(hash));
+            let head = match (&self.tag, &self.describe) {
+                (Some(tag), _) => DetachedRef::tag(tag.clone()),
+                (None, Some(describe)) => {
+                    DetachedRef::Describe(describe.clone().with_link(link(&describe.commit)))
+                }
+                (None, None) => {
+                    DetachedRef::Commit(Commit::new(commit.clone()).with_link(link(commit)))
+                }
+            };
+
+            return Prompt::detached(
+                head,
+                working_tree,
+                index,
+                self.submodules,
+                self.stash,
+                self.worktree,
+            );
+        };
+
+        let remote_diverge = self.upstream.as_deref().map(|name| {
+            // a local branch as upstream has no remote, git calls that remote `.`
+            let (remote, branch) = name.split_once('/').unwrap_or((".", name));
+            (
+                RemoteBranch::new(remote.to_owned(), branch.to_owned())
+                    .with_gone(self.upstream_gone),
+                (self.ahead + self.behind != 0).then(|| Divergence::new(self.ahead, self.behind)),
+            )
+        });
+
+        let push = self.push.as_ref().and_then(|push| {
+            let (remote, branch) = push.name.split_once('/')?;
+            Some((
+                RemoteBranch::new(remote.to_owned(), branch.to_owned()),
+                push.divergence(),
+            ))
+        });
+
+        // a gone upstream has no page left
+        let link = self
+            .forge
+            .as_ref()
+            .zip(remote_diverge.as_ref().filter(|_| !self.upstream_gone))
+            .map(|(forge, (remote, _))| forge.branch_url(remote.branch()));
+
+        let fallback = self
+            .fallback
+            .as_ref()
+            .map(|fallback| (fallback.name.clone(), fallback.divergence()));
+
+        let branch = Branch::new(local.clone(), remote_diverge)
+            .with_push(push)
+            .with_fallback(fallback)
+            .with_tag(self.tag.clone().map(Tag::new))
+            .with_ticket(self.ticket.clone())
+            .with_age(self.age)
+            .with_commits(self.commits)
+            .with_stacked(self.stacked)
+            .with_link(link);
+
+        if working_tree.any() || index.any() || self.submodules.any() {
+            return Prompt::working(
+                branch,
+                working_tree,
+                index,
+                self.submodules,
+                self.stash,
+                self.worktree,
+            )
+            .with_subject(self.subject.clone());
+        }
+
+        Prompt::clean(branch, self.stash, self.worktree).with_subject(self.subject.clone())
+    }
+}
@@ -0,0 +1,623 @@
+use std::collections::HashMap;
+
+use super::{Change, Severity};
+
+/// What a piece of the prompt means, [`RenderOptions::paint`](super::RenderOptions::paint)
+/// decides how that looks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    BranchName,
+    RemoteName,
+    /// `[headless]`, no commit checked out and no branch either.
+    Headless,
+    /// `[.git]`, inside the git dir rather than a work tree.
+    GitDir,
+    Commit,
+    Tag,
+    Ticket,
+    /// The empty brackets of a branch level with what it is compared against.
+    InSync,
+    Diverged,
+    /// Age and commits relative to the base branch.
+    Base,
+    Worktree,
+    Stash,
+    PullRequest,
+    /// The `w`, `i` and `sub` labels.
+    WorkingTree,
+    Index,
+    Submodules,
+    Added,
+    Modified,
+    Removed,
+    Renamed,
+    Copied,
+    TypeChanged,
+    Submodule,
+    Untracked,
+    Conflict,
+    /// The severity of a repo which is ahead only.
+    Ahead,
+    Success,
+    Warning,
+    Error,
+    /// Context rather than state, e.g. subjects and ages.
+    Muted,
+    /// `[busy]` and `[error]` in place of the prompt.
+    Busy,
+    Failed,
+}
+
+/// The 16 colors every terminal has in ANSI order, then the 256 color palette and 24-bit colors
+/// most terminals have today.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    LightBlack,
+    LightRed,
+    LightGreen,
+    LightYellow,
+    LightBlue,
+    LightMagenta,
+    LightCyan,
+    LightWhite,
+    /// A color of the 256 color palette, 0 to 15 are the named ones.
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    /// The named colors, in ANSI order.
+    pub const ALL: [Color; 16] = [
+        Color::Black,
+        Color::Red,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+        Color::White,
+        Color::LightBlack,
+        Color::LightRed,
+        Color::LightGreen,
+        Color::LightYellow,
+        Color::LightBlue,
+        Color::LightMagenta,
+        Color::LightCyan,
+        Color::LightWhite,
+    ];
+
+    // the palette of xterm, which most terminals copied
+    const PALETTE: [(u8, u8, u8); 16] = [
+        (0x00, 0x00, 0x00),
+        (0xcd, 0x00, 0x00),
+        (0x00, 0xcd, 0x00),
+        (0xcd, 0xcd, 0x00),
+        (0x00, 0x00, 0xee),
+        (0xcd, 0x00, 0xcd),
+        (0x00, 0xcd, 0xcd),
+        (0xe5, 0xe5, 0xe5),
+        (0x7f, 0x7f, 0x7f),
+        (0xff, 0x00, 0x00),
+        (0x00, 0xff, 0x00),
+        (0xff, 0xff, 0x00),
+        (0x5c, 0x5c, 0xff),
+        (0xff, 0x00, 0xff),
+        (0x00, 0xff, 0xff),
+        (0xff, 0xff, 0xff),
+    ];
+
+    /// Parses a name, e.g. `light-blue`, a palette index, e.g. `208`, or a 24-bit color as
+    /// `#ff8700` or `rgb(255,135,0)`.
+    pub fn parse(src: &str) -> Option<Self> {
+        if let Some(named) = Self::ALL
+            .into_iter()
+            .find(|color| color.name() == Some(src))
+        {
+            return Some(named);
+        }
+
+        if let Some(hex) = src.strip_prefix('#').filter(|hex| hex.len() == 6) {
+            let channel = |at: usize| u8::from_str_radix(hex.get(at..at + 2)?, 16).ok();
+            return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+        }
+
+        if let Some(channels) = src.strip_prefix("rgb(").and_then(|s| s.strip_suffix(')')) {
+            let mut channels = channels
+                .split(',')
+                .map(|channel| channel.trim().parse().ok());
+            return match (
+                channels.next()?,
+                channels.next()?,
+                channels.next()?,
+                channels.next(),
+            ) {
+                (Some(r), Some(g), Some(b), None) => Some(Color::Rgb(r, g, b)),
+                _ => None,
+            };
+        }
+
+        src.parse().ok().map(Color::Indexed)
+    }
+
+    /// The kebab-case name of the named colors, e.g. `light-blue`.
+    pub fn name(self) -> Option<&'static str> {
+        Some(match self {
+            Color::Black => "black",
+            Color::Red => "red",
+            Color::Green => "green",
+            Color::Yellow => "yellow",
+            Color::Blue => "blue",
+            Color::Magenta => "magenta",
+            Color::Cyan => "cyan",
+            Color::White => "white",
+            Color::LightBlack => "light-black",
+            Color::LightRed => "light-red",
+            Color::LightGreen => "light-green",
+            Color::LightYellow => "light-yellow",
+            Color::LightBlue => "light-blue",
+            Color::LightMagenta => "light-magenta",
+            Color::LightCyan => "light-cyan",
+            Color::LightWhite => "light-white",
+            Color::Indexed(_) | Color::Rgb(..) => return None,
+        })
+    }
+
+    /// The number in the 256 color palette, `None` for 24-bit colors.
+    pub fn index(self) -> Option<u8> {
+        match self {
+            Color::Indexed(index) => Some(index),
+            Color::Rgb(..) => None,
+            named => Self::ALL
+                .iter()
+                .position(|&color| color == named)
+                .map(|index| index as u8),
+        }
+    }
+
+    /// The 24-bit color, the palette as xterm has it.
+    pub fn rgb(self) -> (u8, u8, u8) {
+        let index = match self {
+            Color::Rgb(r, g, b) => return (r, g, b),
+            color => color.index().expect("only 24-bit colors have no index"),
+        };
+
+        // a 6x6x6 cube, then 24 grays
+        let level = |n: u8| if n == 0 { 0 } else { 55 + n * 40 };
+        match index {
+            0..=15 => Self::PALETTE[index as usize],
+            16..=231 => {
+                let n = index - 16;
+                (level(n / 36), level(n / 6 % 6), level(n % 6))
+            }
+            _ => {
+                let gray = 8 + (index - 232) * 10;
+                (gray, gray, gray)
+            }
+        }
+    }
+
+    /// The color as CSS, a custom property `--epg-<color>` falling back to the xterm palette for
+    /// the named colors.
+    pub fn css(self) -> String {
+        let (r, g, b) = self.rgb();
+        match self.name() {
+            Some(name) => format!("var(--epg-{name}, #{r:02x}{g:02x}{b:02x})"),
+            None => format!("#{r:02x}{g:02x}{b:02x}"),
+        }
+    }
+}
+
+/// How a [`Role`] looks, the default is no styling at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bold: bool,
+}
+
+impl Style {
+    pub const fn fg(color: Color) -> Self {
+        Self {
+            fg: Some(color),
+            bold: false,
+        }
+    }
+
+    pub const fn bold(self) -> Self {
+        Self { bold: true, ..self }
+    }
+
+    pub fn is_plain(&self) -> bool {
+        self.fg.is_none() && !self.bold
+    }
+
+    /// Parses a color and `bold` in any order, e.g. `light-blue bold` or `#ff8700`, `plain` for
+    /// no styling at all, see [`Color::parse`].
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let mut style = Self::default();
+        for word in src.split_whitespace() {
+            match word {
+                "bold" => style.bold = true,
+                "plain" => {}
+                word => {
+                    let color =
+                        Color::parse(word).ok_or_else(|| format!("unknown color `{word}`"))?;
+                    if style.fg.replace(color).is_some() {
+                        return Err(format!("more than one color in `{src}`"));
+                    }
+                }
+            }
+        }
+
+        Ok(style)
+    }
+}
+
+impl Role {
+    pub const ALL: [Role; 32] = [
+        Role::BranchName,
+        Role::RemoteName,
+        Role::Headless,
+        Role::GitDir,
+        Role::Commit,
+        Role::Tag,
+        Role::Ticket,
+        Role::InSync,
+        Role::Diverged,
+        Role::Base,
+        Role::Worktree,
+        Role::Stash,
+        Role::PullRequest,
+        Role::WorkingTree,
+        Role::Index,
+        Role::Submodules,
+        Role::Added,
+        Role::Modified,
+        Role::Removed,
+        Role::Renamed,
+        Role::Copied,
+        Role::TypeChanged,
+        Role::Submodule,
+        Role::Untracked,
+        Role::Conflict,
+        Role::Ahead,
+        Role::Success,
+        Role::Warning,
+        Role::Error,
+        Role::Muted,
+        Role::Busy,
+        Role::Failed,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|role| role.name() == name)
+    }
+
+    /// The kebab-case name, e.g. the CSS class of `--output=html` is `epg-<name>`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::BranchName => "branch-name",
+            Role::RemoteName => "remote-name",
+            Role::Headless => "headless",
+            Role::GitDir => "git-dir",
+            Role::Commit => "commit",
+            Role::Tag => "tag",
+            Role::Ticket => "ticket",
+            Role::InSync => "in-sync",
+            Role::Diverged => "diverged",
+            Role::Base => "base",
+            Role::Worktree => "worktree",
+            Role::Stash => "stash",
+            Role::PullRequest => "pull-request",
+            Role::WorkingTree => "working-tree",
+            Role::Index => "index",
+            Role::Submodules => "submodules",
+            Role::Added => "added",
+            Role::Modified => "modified",
+            Role::Removed => "removed",
+            Role::Renamed => "renamed",
+            Role::Copied => "copied",
+            Role::TypeChanged => "type-changed",
+            Role::Submodule => "submodule",
+            Role::Untracked => "untracked",
+            Role::Conflict => "conflict",
+            Role::Ahead => "ahead",
+            Role::Success => "success",
+            Role::Warning => "warning",
+            Role::Error => "error",
+            Role::Muted => "muted",
+            Role::Busy => "busy",
+            Role::Failed => "failed",
+        }
+    }
+
+    /// The built-in look of each role.
+    pub fn style(self) -> Style {
+        match self {
+            Role::BranchName => Style::default(),
+            Role::RemoteName => Style::fg(Color::Blue),
+            Role::Headless | Role::GitDir => Style::fg(Color::Blue).bold(),
+            Role::Commit | Role::Tag => Style::fg(Color::Yellow).bold(),
+            Role::Ticket => Style::fg(Color::Magenta).bold(),
+            Role::InSync | Role::Index | Role::Added | Role::Success => Style::fg(Color::Green),
+            Role::Diverged | Role::Removed | Role::Error => Style::fg(Color::Red),
+            Role::Base | Role::Worktree | Role::PullRequest | Role::Submodules | Role::Renamed => {
+                Style::fg(Color::Cyan)
+            }
+            Role::Stash | Role::TypeChanged => Style::fg(Color::Magenta),
+            Role::WorkingTree | Role::Modified | Role::Warning => Style::fg(Color::Yellow),
+            Role::Copied => Style::fg(Color::LightCyan),
+            Role::Submodule | Role::Ahead => Style::fg(Color::Blue),
+            Role::Untracked | Role::Muted => Style::fg(Color::LightBlack),
+            Role::Conflict | Role::Failed => Style::fg(Color::Red).bold(),
+            Role::Busy => Style::fg(Color::Yellow).bold(),
+        }
+    }
+}
+
+/// A symbol marking a part of the prompt, how it is drawn depends on the [`Theme`] and on
+/// [`RenderOptions::ascii`](super::RenderOptions::ascii).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Glyph {
+    /// In front of the local branch, nothing by default.
+    Branch,
+    Ahead,
+    Behind,
+    /// The `w` of `w[~2]`.
+    WorkingTree,
+    /// The `i` of `i[+1]`.
+    Index,
+    /// The `s` of `s[1]`.
+    Stash,
+    /// The `!` of `[!3]`.
+    Conflict,
+    /// The `<-` of `main <- feature` while merging.
+    Merge,
+    /// The `->` of `feature -> main` while rebasing.
+    Rebase,
+    /// The `+`, `~`, `-` and so on in front of the count of each kind of change.
+    Change(Change),
+    /// The upstream no longer exists.
+    Gone,
+    /// Branches stacked on top, see `stack.enabled`.
+    Stacked,
+    /// Where `git push` pushes to, see `branch.push`.
+    Push,
+    /// A linked worktree.
+    Worktree,
+    /// The last fetch is long ago, see `fetch.stale`.
+    Stale,
+    /// Files hidden from status, see `status.hidden`.
+    Hidden,
+    /// In front of deleted lines, see `diff.stat`.
+    Minus,
+    /// HEAD was switched recently, see `head.switched`.
+    Switched,
+    /// Parts of the status were skipped as it was slow, see `adaptive`.
+    Reduced,
+    /// Marks text cut short, e.g. long subjects.
+    Ellipsis,
+    CiSuccess,
+    CiFailure,
+    CiPending,
+    /// A signature on HEAD, good or not, see `head.signature`.
+    Signed,
+    Unsigned,
+    /// The hints of `hint.style = "glyph"`.
+    HintPull,
+    HintPush,
+    HintBlocked,
+    /// The single symbol of `--output=symbol`.
+    Severity(Severity),
+}
+
+impl Glyph {
+    /// How the glyph is drawn, every glyph in every theme and in ASCII in one place so none is
+    /// forgotten.
+    pub fn text(self, theme: Theme, ascii: bool) -> &'static str {
+        let (plain, default, icons) = match self {
+            // nf-pl-branch
+            Glyph::Branch => ("", "", "\u{e0a0}"),
+            // nf-oct-arrow_small_up and nf-oct-arrow_small_down
+            Glyph::Ahead => (">", "↑", "\u{f47b}"),
+            Glyph::Behind => ("<", "↓", "\u{f47c}"),
+            // nf-fa-pencil, nf-fa-check, nf-fa-archive and nf-fa-warning
+            Glyph::WorkingTree => ("w", "w", "\u{f040}"),
+            Glyph::Index => ("i", "i", "\u{f00c}"),
+            Glyph::Stash => ("s", "s", "\u{f187}"),
+            Glyph::Conflict => ("!", "!", "\u{f071}"),
+            Glyph::Merge => ("<-", "<-", "<-"),
+            Glyph::Rebase => ("->", "->", "->"),
+            Glyph::Change(Change::Add) => ("+", "+", "+"),
+            Glyph::Change(Change::Mod) => ("~", "~", "~"),
+            Glyph::Change(Change::Del) => ("-", "-", "-"),
+            Glyph::Change(Change::Ren) => ("*", "*", "*"),
+            Glyph::Change(Change::Cpy) => ("&", "&", "&"),
+            Glyph::Change(Change::Typ) => ("?", "?", "?"),
+            Glyph::Change(Change::Sub) => ("@", "@", "@"),
+            Glyph::Change(Change::Untracked) => ("%", "%", "%"),
+            Glyph::Change(Change::UntrackedDir) => ("%/", "%/", "%/"),
+            Glyph::Gone => ("x", "✗", "✗"),
+            Glyph::Stacked => ("=", "≡", "≡"),
+            Glyph::Push => ("->", "→", "→"),
+            Glyph::Worktree => ("wt", "⎇wt", "⎇wt"),
+            Glyph::Stale => ("stale:", "⟳", "⟳"),
+            Glyph::Hidden => ("hidden:", "⊘", "⊘"),
+            Glyph::Minus => ("-", "−", "−"),
+            Glyph::Switched => ("sw", "↻", "↻"),
+            Glyph::Reduced => ("slow", "⏱", "⏱"),
+            Glyph::Ellipsis => ("...", "…", "…"),
+            Glyph::CiSuccess => ("ok", "✓", "✓"),
+            Glyph::CiFailure => ("fail", "✗", "✗"),
+            Glyph::CiPending => ("pending", "●", "●"),
+            Glyph::Signed => ("sig", "🔒", "🔒"),
+            Glyph::Unsigned => ("nosig", "🔓", "🔓"),
+            Glyph::HintPull => ("<", "⇣", "⇣"),
+            Glyph::HintPush => (">", "⇡", "⇡"),
+            Glyph::HintBlocked => ("x", "⛔", "⛔"),
+            Glyph::Severity(Severity::Clean) => ("=", "✓", "✓"),
+            Glyph::Severity(Severity::Ahead) => (">", "↑", "↑"),
+            Glyph::Severity(Severity::Dirty) => ("*", "●", "●"),
+            Glyph::Severity(Severity::Behind) => ("<", "↓", "↓"),
+            Glyph::Severity(Severity::Conflict) => ("!", "✖", "✖"),
+        };
+
+        match (ascii, theme) {
+            (true, _) => plain,
+            (false, Theme::Default) => default,
+            (false, Theme::Icons) => icons,
+        }
+    }
+}
+
+/// Glyphs, styles and counts as the user configured them rather than as the theme has them, e.g.
+/// by `symbol.ahead`, `color.<role>` or `count.cap`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Overrides {
+    glyphs: HashMap<Glyph, String>,
+    styles: HashMap<Role, Style>,
+    separator: Option<String>,
+    thresholds: HashMap<Change, usize>,
+    cap: Option<usize>,
+}
+
+impl Overrides {
+    pub fn set_glyph(&mut self, glyph: Glyph, text: &str) {
+        self.glyphs.insert(glyph, text.to_owned());
+    }
+
+    pub fn glyph(&self, glyph: Glyph) -> Option<&str> {
+        self.glyphs.get(&glyph).map(String::as_str)
+    }
+
+    pub fn set_style(&mut self, role: Role, style: Style) {
+        self.styles.insert(role, style);
+    }
+
+    pub fn style(&self, role: Role) -> Option<Style> {
+        self.styles.get(&role).copied()
+    }
+
+    /// Leaves out counts of `change` up to `threshold`, see `count.<change>`.
+    pub fn set_threshold(&mut self, change: Change, threshold: usize) {
+        self.thresholds.insert(change, threshold);
+    }
+
+    pub fn threshold(&self, change: Change) -> usize {
+        self.thresholds.get(&change).copied().unwrap_or(0)
+    }
+
+    /// Writes counts above `cap` as `<cap>+`, see `count.cap`.
+    pub fn set_cap(&mut self, cap: usize) {
+        self.cap = Some(cap);
+    }
+
+    pub fn cap(&self) -> Option<usize> {
+        self.cap
+    }
+
+    /// Sets what goes between the parts of the prompt in place of `::`, see `separator`.
+    pub fn set_separator(&mut self, text: &str) {
+        self.separator = Some(text.to_owned());
+    }
+
+    pub fn separator(&self) -> Option<&str> {
+        self.separator.as_deref()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+            && self.styles.is_empty()
+            && self.separator.is_none()
+            && self.thresholds.is_empty()
+            && self.cap.is_none()
+    }
+}
+
+/// The background of the terminal, the built-in styles are tuned for dark ones, see `background`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Background {
+    #[default]
+    Dark,
+    /// Yellow and the light colors are swapped for darker ones, which read on white.
+    Light,
+}
+
+impl Background {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Background::Dark),
+            "light" => Some(Background::Light),
+            _ => None,
+        }
+    }
+
+    /// The background as rxvt and others export it, `<fg>;<bg>` or `<fg>;<xpm>;<bg>` with `bg`
+    /// a palette index, 7 and 9 to 15 are light.
+    pub fn from_colorfgbg(value: &str) -> Option<Self> {
+        match value.rsplit(';').next()?.parse::<u8>().ok()? {
+            7 | 9..=15 => Some(Background::Light),
+            _ => Some(Background::Dark),
+        }
+    }
+
+    /// `color` as it reads best on this background.
+    pub fn adapt(self, color: Color) -> Color {
+        match (self, color) {
+            (Background::Dark, color) => color,
+            // dark goldenrod
+            (Background::Light, Color::Yellow | Color::LightYellow) => Color::Indexed(136),
+            (Background::Light, Color::LightGreen) => Color::Green,
+            (Background::Light, Color::LightCyan) => Color::Cyan,
+            (Background::Light, Color::LightBlue) => Color::Blue,
+            (Background::Light, Color::LightMagenta) => Color::Magenta,
+            (Background::Light, Color::LightRed) => Color::Red,
+            (Background::Light, Color::White | Color::LightWhite) => Color::Black,
+            (Background::Light, color) => color,
+        }
+    }
+}
+
+/// A built-in look of the whole prompt, see `theme` and `--theme`, compared side by side by
+/// `gallery`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Theme {
+    /// The style of each [`Role`] as [`Role::style`] has it.
+    #[default]
+    Default,
+    /// [`Theme::Default`] with Nerd Font icons in place of the letters of [`Glyph`]s.
+    Icons,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 2] = [Theme::Default, Theme::Icons];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Theme::Default),
+            "icons" => Some(Theme::Icons),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Default => "default",
+            Theme::Icons => "icons",
+        }
+    }
+
+    /// How `role` looks in this theme.
+    pub fn style(self, role: Role) -> Style {
+        match self {
+            Theme::Default | Theme::Icons => role.style(),
+        }
+    }
+}
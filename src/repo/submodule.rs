@@ -0,0 +1,74 @@
+use std::fmt::{self, Debug, Display, Write};
+
+use super::{RenderOptions, Role};
+
+/// Counts of submodules by state, a submodule may be counted in more than one state.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Submodules {
+    commit: usize,
+    modified: usize,
+    untracked: usize,
+}
+
+impl Submodules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // S<c><m><u>
+    // c    `C` if the checked out commit differs from the recorded one
+    // m    `M` if it has tracked changes
+    // u    `U` if it has untracked changes
+    pub fn add(&mut self, state: &str) {
+        let state = state.as_bytes();
+        self.commit += (state.get(1) == Some(&b'C')) as usize;
+        self.modified += (state.get(2) == Some(&b'M')) as usize;
+        self.untracked += (state.get(3) == Some(&b'U')) as usize;
+    }
+
+    pub fn counts(&self) -> [(&'static str, usize); 3] {
+        [
+            ("commit", self.commit),
+            ("modified", self.modified),
+            ("untracked", self.untracked),
+        ]
+    }
+
+    pub fn any(&self) -> bool {
+        self.commit != 0 || self.modified != 0 || self.untracked != 0
+    }
+}
+
+impl Debug for Submodules {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Submodules")
+            .field("commit", &self.commit)
+            .field("modified", &self.modified)
+            .field("untracked", &self.untracked)
+            .finish()
+    }
+}
+
+impl Submodules {
+    pub fn render_to(&self, out: &mut dyn Write, options: &RenderOptions) -> fmt::Result {
+        let counts = [
+            ('+', self.commit, Role::Submodules),
+            ('~', self.modified, Role::Modified),
+            ('?', self.untracked, Role::Untracked),
+        ];
+
+        for (symbol, count, role) in counts.into_iter().filter(|&(_, count, _)| count != 0) {
+            options.paint(out, role, format_args!("{symbol}{count}"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Submodules {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let options = RenderOptions::from_formatter(f);
+        self.render_to(f, &options)
+    }
+}
@@ -0,0 +1,297 @@
+use std::fmt::{self, Write};
+
+use super::{Glyph, Prompt, RenderOptions, Role, SUBJECT_LEN};
+use crate::util;
+
+/// A piece of the prompt a [`Template`] can place, named as in the template.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segment {
+    /// `branch`, the local branch or whatever HEAD is instead, e.g. `[headless]` or `a <- b`.
+    Branch,
+    /// `remote`, the upstream.
+    Remote,
+    /// `div`, the divergence from the upstream, nothing if level.
+    Div,
+    /// `push`, where `git push` pushes to, see `branch.push`.
+    Push,
+    PushDiv,
+    /// `fallback`, the base branch compared against without an upstream, see `branch.fallback`.
+    Fallback,
+    FallbackDiv,
+    /// `age`, `commits` and `stacked`, see `branch.age`, `branch.commits` and `stack.enabled`.
+    Age,
+    Commits,
+    Stacked,
+    Tag,
+    Worktree,
+    Stash,
+    Conflicts,
+    /// `wt`, `idx` and `sub`, changes in the working tree, index and submodules.
+    WorkingTree,
+    Index,
+    Submodules,
+    Subject,
+}
+
+impl Segment {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "branch" => Some(Self::Branch),
+            "remote" => Some(Self::Remote),
+            "div" => Some(Self::Div),
+            "push" => Some(Self::Push),
+            "push_div" => Some(Self::PushDiv),
+            "fallback" => Some(Self::Fallback),
+            "fallback_div" => Some(Self::FallbackDiv),
+            "age" => Some(Self::Age),
+            "commits" => Some(Self::Commits),
+            "stacked" => Some(Self::Stacked),
+            "tag" => Some(Self::Tag),
+            "worktree" => Some(Self::Worktree),
+            "stash" => Some(Self::Stash),
+            "conflicts" => Some(Self::Conflicts),
+            "wt" => Some(Self::WorkingTree),
+            "idx" => Some(Self::Index),
+            "sub" => Some(Self::Submodules),
+            "subject" => Some(Self::Subject),
+            _ => None,
+        }
+    }
+}
+
+/// A set of [`Segment`]s, e.g. those hidden by `--no-stash` and the like.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Segments(u32);
+
+impl Segments {
+    pub fn insert(&mut self, segment: Segment) {
+        self.0 |= 1 << segment as u32;
+    }
+
+    pub fn contains(self, segment: Segment) -> bool {
+        self.0 & 1 << segment as u32 != 0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    /// The segment between `before` and `after`, all left out if the segment is empty.
+    Segment {
+        segment: Segment,
+        before: String,
+        after: String,
+    },
+}
+
+/// A layout replacing the built-in one, see `format` and `--format`.
+///
+/// `{name}` places a segment, `{name:[{}]}` wraps it in text left out along with an empty
+/// segment and `{{` and `}}` are literal braces, e.g. `{branch}{remote:[{}]}{div} :: w[{wt}]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template(Vec<Part>);
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let mut parts = vec![];
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.next_if_eq(&'{').is_some() => text.push('{'),
+                '}' if chars.next_if_eq(&'}').is_some() => text.push('}'),
+                '}' => return Err("unmatched `}`, use `}}` for a literal one".to_owned()),
+                '{' => {
+                    let mut name = String::new();
+                    let mut wrap = None;
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(':') if wrap.is_none() => wrap = Some(String::new()),
+                            // `{}` marks the segment within the wrap
+                            Some('{') if wrap.is_some() && chars.next_if_eq(&'}').is_some() => {
+                                wrap.as_mut().expect("checked").push_str("{}")
+                            }
+                            Some(c) => wrap.as_mut().unwrap_or(&mut name).push(c),
+                            None => return Err(format!("unclosed `{{{name}`")),
+                        }
+                    }
+
+                    let segment = Segment::from_name(&name)
+                        .ok_or_else(|| format!("unknown segment `{name}`"))?;
+                    let (before, after) = match &wrap {
+                        Some(wrap) => wrap
+                            .split_once("{}")
+                            .filter(|(_, after)| !after.contains("{}"))
+                            .ok_or_else(|| format!("`{name}` needs exactly one `{{}}`"))?,
+                        None => ("", ""),
+                    };
+
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Segment {
+                        segment,
+                        before: before.to_owned(),
+                        after: after.to_owned(),
+                    });
+                }
+                c => text.push(c),
+            }
+        }
+
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(Self(parts))
+    }
+
+    /// The segments in the order of `names`, decorated as in the built-in layout, see `order`.
+    ///
+    /// The brackets of the upstream and the base follow the segment before them directly, e.g.
+    /// `main[origin/~][↑1]`, anything else is separated by a space.
+    pub fn from_order<'n>(names: impl IntoIterator<Item = &'n str>) -> Result<Self, String> {
+        let mut parts = vec![];
+        for name in names {
+            let segment =
+                Segment::from_name(name).ok_or_else(|| format!("unknown segment `{name}`"))?;
+            let (before, after) = match segment {
+                Segment::Remote
+                | Segment::Div
+                | Segment::Push
+                | Segment::PushDiv
+                | Segment::Fallback
+                | Segment::FallbackDiv => ("[", "]"),
+                Segment::Conflicts => (" [", "]"),
+                Segment::Stash => (" s[", "]"),
+                Segment::WorkingTree => (" w[", "]"),
+                Segment::Index => (" i[", "]"),
+                Segment::Submodules => (" sub[", "]"),
+                _ => (" ", ""),
+            };
+
+            // nothing to separate the first segment from
+            let before = match parts.is_empty() {
+                true => before.trim_start(),
+                false => before,
+            };
+            parts.push(Part::Segment {
+                segment,
+                before: before.to_owned(),
+                after: after.to_owned(),
+            });
+        }
+
+        Ok(Self(parts))
+    }
+}
+
+impl Prompt {
+    /// The prompt laid out by `template` rather than the built-in layout.
+    pub fn render_template(&self, template: &Template, options: &RenderOptions) -> String {
+        let mut out = String::new();
+        let mut segment_out = String::new();
+
+        for part in &template.0 {
+            match part {
+                Part::Text(text) => options
+                    .text(&mut out, text)
+                    .expect("writing to a String does not fail"),
+                Part::Segment {
+                    segment,
+                    before,
+                    after,
+                } => {
+                    segment_out.clear();
+                    self.render_segment(*segment, &mut segment_out, options)
+                        .expect("writing to a String does not fail");
+
+                    if !segment_out.is_empty() {
+                        options
+                            .text(&mut out, before)
+                            .and_then(|()| out.write_str(&segment_out))
+                            .and_then(|()| options.text(&mut out, after))
+                            .expect("writing to a String does not fail");
+                    }
+                }
+            }
+        }
+
+        out
+    }
+
+    /// A single segment without the decoration of the built-in layout, nothing if it does not
+    /// apply.
+    pub fn render_segment(
+        &self,
+        segment: Segment,
+        out: &mut dyn Write,
+        options: &RenderOptions,
+    ) -> fmt::Result {
+        if options.hidden.contains(segment) {
+            return Ok(());
+        }
+
+        let (worktree, subject, conflicts) = match self {
+            Prompt::Headless { .. } => (false, None, None),
+            Prompt::Clean {
+                worktree, subject, ..
+            }
+            | Prompt::Working {
+                worktree, subject, ..
+            } => (*worktree, subject.as_deref(), None),
+            Prompt::Detached { worktree, .. } => (*worktree, None, None),
+            Prompt::Conflicted {
+                worktree,
+                conflicts,
+                ..
+            } => (*worktree, None, Some(conflicts)),
+        };
+
+        match segment {
+            Segment::Branch => self.render_head(out, options)?,
+            Segment::Worktree if worktree => {
+                options.paint(out, Role::Worktree, options.glyph(Glyph::Worktree))?
+            }
+            Segment::Stash if self.stash() != 0 => options.paint(out, Role::Stash, self.stash())?,
+            Segment::Conflicts => {
+                if let Some(conflicts) = conflicts.filter(|conflicts| conflicts.any()) {
+                    conflicts.render_to(out, options)?;
+                }
+            }
+            Segment::WorkingTree => {
+                let working_tree = self.working_tree();
+                if let Some(changes) = working_tree.filter(|changes| changes.any_shown(options)) {
+                    changes.render_to(out, options)?;
+                }
+            }
+            Segment::Index => {
+                if let Some(changes) = self.index().filter(|changes| changes.any_shown(options)) {
+                    changes.render_to(out, options)?;
+                }
+            }
+            Segment::Submodules => {
+                if let Some(submodules) = self.submodules().filter(|submodules| submodules.any()) {
+                    submodules.render_to(out, options)?;
+                }
+            }
+            Segment::Subject => {
+                if let Some(subject) = subject {
+                    let ellipsis = options.glyph(Glyph::Ellipsis);
+                    let truncated = util::truncate(subject, SUBJECT_LEN, ellipsis);
+                    options.paint(out, Role::Muted, format_args!("\"{truncated}\""))?;
+                }
+            }
+            Segment::Worktree | Segment::Stash => {}
+            _ => {
+                if let Some(branch) = self.branch() {
+                    branch.render_segment(segment, out, options)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+use std::{
+    io::Read,
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{cache, config, util};
+
+const DEFAULT_TTL: u64 = 5 * 60;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(1000);
+
+// how often a running command is checked on
+const POLL: Duration = Duration::from_millis(10);
+
+/// A user configured command whose first line of output is shown as a segment, e.g. the CI status
+/// of a branch. Commands are slow and talk to the network, so their output is cached per repo and
+/// branch for `<prefix>.ttl` seconds and they are killed after `<prefix>.timeout` milliseconds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Custom<'c> {
+    prefix: &'static str,
+    command: &'c str,
+    ttl: u64,
+    timeout: Duration,
+}
+
+impl<'c> Custom<'c> {
+    /// The segment configured under `<prefix>.command`, `None` if there is no command.
+    pub fn from_config(prefix: &'static str, config: &'c config::Config) -> Option<Self> {
+        let int = |key: &str| {
+            config
+                .get_int(&format!("{prefix}.{key}"))
+                .map(|v| v.max(0) as u64)
+        };
+
+        Some(Self {
+            prefix,
+            command: config.get_str(&format!("{prefix}.command"))?,
+            ttl: int("ttl").unwrap_or(DEFAULT_TTL),
+            timeout: int("timeout").map_or(DEFAULT_TIMEOUT, Duration::from_millis),
+        })
+    }
+
+    /// The output for `branch` in the repo at `path`, `None` if the command failed, timed out or
+    /// printed nothing, which is cached just the same to not stall every prompt. Timeouts are
+    /// added to `warnings`.
+    pub fn output(
+        &self,
+        path: &Path,
+        git_dir: &Path,
+        branch: &str,
+        warnings: &mut Vec<String>,
+    ) -> Option<String> {
+        let key = cache::hash_key(
+            format!(
+                "{}\0{branch}\0{}",
+                util::stable_path(git_dir).display(),
+                self.command
+            )
+            .as_bytes(),
+        );
+        let kind = format!("segment-{}", self.prefix);
+
+        // <unix secs>\n<output>
+        let cached = cache::get(&kind, &key).and_then(|entry| {
+            let (at, output) = entry.split_once('\n')?;
+            let at: u64 = at.parse().ok()?;
+            (util::unix_now().saturating_sub(at) < self.ttl).then(|| output.to_owned())
+        });
+
+        let output = match cached {
+            Some(output) => output,
+            None => {
+                let output = self.run(path, branch).unwrap_or_else(|timeout| {
+                    warnings.push(format!(
+                        "`{}.command` timed out after {}ms, nothing is shown for {}s",
+                        self.prefix,
+                        timeout.as_millis(),
+                        self.ttl
+                    ));
+                    String::new()
+                });
+                let _ = cache::set(&kind, &key, &format!("{}\n{output}", util::unix_now()));
+                output
+            }
+        };
+
+        (!output.is_empty()).then_some(output)
+    }
+
+    // the timeout if the command was killed, anything else going wrong is no output
+    fn run(&self, path: &Path, branch: &str) -> Result<String, Duration> {
+        let mut command = if cfg!(windows) {
+            let mut command = Command::new("cmd");
+            command.arg("/C");
+            command
+        } else {
+            let mut command = Command::new("sh");
+            command.arg("-c");
+            command
+        };
+
+        let child = command
+            .arg(self.command)
+            .current_dir(path)
+            .env("EPB_PROMPT_GIT_BRANCH", branch)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn();
+        let Ok(mut child) = child else {
+            return Ok(String::new());
+        };
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) if status.success() => break,
+                Ok(None) if Instant::now() < deadline => thread::sleep(POLL),
+                status => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return match status {
+                        Ok(None) => Err(self.timeout),
+                        _ => Ok(String::new()),
+                    };
+                }
+            }
+        }
+
+        let mut stdout = String::new();
+        if let Some(mut out) = child.stdout.take() {
+            let _ = out.read_to_string(&mut stdout);
+        }
+        Ok(stdout.lines().next().unwrap_or_default().trim().to_owned())
+    }
+}
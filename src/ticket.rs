@@ -0,0 +1,234 @@
+use crate::config;
+
+// matches `PROJ-123` style ids anywhere in the branch name
+const DEFAULT_PATTERN: &str = r"[A-Z][A-Z0-9]+-\d+";
+
+/// An issue tracker id extracted from a branch name, see `ticket.pattern`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ticket {
+    pub id: String,
+    /// The branch name without the id and the separators around it, empty if it was only the id.
+    pub rest: String,
+    /// `ticket.url` with `{ticket}` replaced by the id.
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Char(char),
+    Any,
+    // inclusive ranges, negated
+    Class(Vec<(char, char)>, bool),
+    Start,
+    End,
+    GroupStart,
+    GroupEnd,
+}
+
+impl Node {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Node::Char(expected) => c == *expected,
+            Node::Any => true,
+            Node::Class(ranges, negated) => {
+                ranges.iter().any(|&(lo, hi)| (lo..=hi).contains(&c)) != *negated
+            }
+            Node::Start | Node::End | Node::GroupStart | Node::GroupEnd => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Item {
+    node: Node,
+    min: usize,
+    max: usize,
+}
+
+/// A small subset of regular expressions, enough for ticket ids: literals, `.`, `[a-z]` classes,
+/// `\d` `\w` `\s`, `*` `+` `?`, `^` `$` and a single capture group selecting the id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern(Vec<Item>);
+
+fn escape(c: char) -> Node {
+    match c {
+        'd' => Node::Class(vec![('0', '9')], false),
+        'w' => Node::Class(vec![('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')], false),
+        's' => Node::Class(vec![(' ', ' '), ('\t', '\t')], false),
+        c => Node::Char(c),
+    }
+}
+
+impl Pattern {
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let mut items: Vec<Item> = vec![];
+        let mut chars = src.chars();
+        let mut groups = 0;
+
+        while let Some(c) = chars.next() {
+            let node = match c {
+                '\\' => escape(chars.next().ok_or("trailing `\\`")?),
+                '.' => Node::Any,
+                '^' => Node::Start,
+                '$' => Node::End,
+                '(' => {
+                    groups += 1;
+                    Node::GroupStart
+                }
+                ')' => Node::GroupEnd,
+                '[' => {
+                    let mut ranges = vec![];
+                    let mut negated = false;
+                    let mut first = true;
+
+                    loop {
+                        let c = match chars.next().ok_or("unclosed `[`")? {
+                            ']' if !first => break,
+                            '^' if first => {
+                                negated = true;
+                                continue;
+                            }
+                            '\\' => chars.next().ok_or("trailing `\\`")?,
+                            c => c,
+                        };
+                        first = false;
+
+                        // `a-z`, a `-` before the `]` is literal
+                        let mut lookahead = chars.clone();
+                        match (lookahead.next(), lookahead.next()) {
+                            (Some('-'), Some(hi)) if hi != ']' => {
+                                chars = lookahead;
+                                ranges.push((c, hi));
+                            }
+                            _ => ranges.push((c, c)),
+                        }
+                    }
+
+                    Node::Class(ranges, negated)
+                }
+                '*' | '+' | '?' => {
+                    let item = items
+                        .last_mut()
+                        .filter(|item| item.min == 1 && item.max == 1)
+                        .filter(|item| {
+                            matches!(item.node, Node::Char(_) | Node::Any | Node::Class(..))
+                        })
+                        .ok_or_else(|| format!("nothing to repeat before `{c}`"))?;
+
+                    (item.min, item.max) = match c {
+                        '*' => (0, usize::MAX),
+                        '+' => (1, usize::MAX),
+                        _ => (0, 1),
+                    };
+                    continue;
+                }
+                c => Node::Char(c),
+            };
+
+            items.push(Item {
+                node,
+                min: 1,
+                max: 1,
+            });
+        }
+
+        let opened = items.iter().filter(|i| i.node == Node::GroupStart).count();
+        let closed = items.iter().filter(|i| i.node == Node::GroupEnd).count();
+        if groups > 1 || opened != closed {
+            return Err("at most one balanced group is supported".to_owned());
+        }
+
+        Ok(Self(items))
+    }
+
+    // the end of the match of `items` at `pos`, recording the group bounds
+    fn match_at(
+        items: &[Item],
+        text: &[char],
+        pos: usize,
+        group: &mut (usize, usize),
+    ) -> Option<usize> {
+        let Some((item, rest)) = items.split_first() else {
+            return Some(pos);
+        };
+
+        match item.node {
+            Node::Start => return (pos == 0).then(|| Self::match_at(rest, text, pos, group))?,
+            Node::End => return (pos == text.len()).then_some(pos),
+            Node::GroupStart => {
+                let start = group.0;
+                group.0 = pos;
+                let end = Self::match_at(rest, text, pos, group);
+                if end.is_none() {
+                    group.0 = start;
+                }
+                return end;
+            }
+            Node::GroupEnd => {
+                let end = group.1;
+                group.1 = pos;
+                let matched = Self::match_at(rest, text, pos, group);
+                if matched.is_none() {
+                    group.1 = end;
+                }
+                return matched;
+            }
+            _ => {}
+        }
+
+        // greedy, backing off one at a time
+        let available = text[pos..]
+            .iter()
+            .take(item.max)
+            .take_while(|&&c| item.node.matches(c))
+            .count();
+
+        (item.min..=available)
+            .rev()
+            .find_map(|n| Self::match_at(rest, text, pos + n, group))
+    }
+
+    /// The char range of the leftmost match, only the group if there is one.
+    fn find(&self, text: &[char]) -> Option<(usize, usize)> {
+        let has_group = self.0.iter().any(|item| item.node == Node::GroupStart);
+
+        (0..=text.len()).find_map(|start| {
+            let mut group = (0, 0);
+            let end = Self::match_at(&self.0, text, start, &mut group)?;
+            Some(if has_group { group } else { (start, end) })
+        })
+    }
+}
+
+/// Extracts a ticket from `branch` using `ticket.pattern`, `None` if tickets are disabled, the
+/// pattern is invalid or nothing matched.
+pub fn extract(branch: &str, config: &config::Config) -> Option<Ticket> {
+    if !config.get_bool("ticket.enabled").unwrap_or(false) {
+        return None;
+    }
+
+    let pattern =
+        Pattern::parse(config.get_str("ticket.pattern").unwrap_or(DEFAULT_PATTERN)).ok()?;
+
+    let text: Vec<char> = branch.chars().collect();
+    let (start, end) = pattern.find(&text).filter(|(start, end)| start != end)?;
+
+    let id: String = text[start..end].iter().collect();
+    // `feature/PROJ-123-login` is `feature/login`
+    let separators: &[char] = &['-', '_', '/', '.'];
+    let before: String = text[..start].iter().collect();
+    let after: String = text[end..].iter().collect();
+    let rest = match after.trim_start_matches(separators) {
+        "" => before.trim_end_matches(separators).to_owned(),
+        after => format!("{before}{after}"),
+    };
+
+    Some(Ticket {
+        url: config
+            .get_str("ticket.url")
+            .map(|template| template.replace("{ticket}", &id)),
+        id,
+        rest,
+    })
+}
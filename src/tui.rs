@@ -0,0 +1,236 @@
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use termion::{clear, color, cursor, raw::IntoRawMode, screen::AlternateScreen, style};
+
+use crate::{cache, config, get_snapshot, repo, util};
+
+// how often the git dir is checked for changes
+const POLL: Duration = Duration::from_millis(200);
+
+// edits to the working tree don't touch the git dir, so refresh regardless every so often
+const REFRESH: Duration = Duration::from_secs(2);
+
+// files in the git dir which change whenever HEAD, the index, refs or the stash do
+const WATCHED: &[&str] = &[
+    "HEAD",
+    "index",
+    "MERGE_HEAD",
+    "CHERRY_PICK_HEAD",
+    "REVERT_HEAD",
+    "REBASE_HEAD",
+    "logs/HEAD",
+    "logs/refs/stash",
+    "packed-refs",
+    cache::STAMP,
+];
+
+fn fingerprint(git_dir: &Path) -> Vec<Option<SystemTime>> {
+    WATCHED
+        .iter()
+        .map(|file| {
+            fs::metadata(git_dir.join(file))
+                .and_then(|m| m.modified())
+                .ok()
+        })
+        .collect()
+}
+
+// <marker>\t<name>\t<upstream>\t<track>, e.g. `*\tmain\torigin/main\t[ahead 1]`
+fn branches(path: &Path) -> io::Result<Vec<String>> {
+    let output = util::git(path)
+        .args([
+            "for-each-ref",
+            "refs/heads",
+            "--format=%(HEAD)\t%(refname:short)\t%(upstream:short)\t%(upstream:track)",
+        ])
+        .output()?;
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| {
+            let mut fields = line.splitn(4, '\t');
+            let (head, name) = (fields.next().unwrap_or(""), fields.next().unwrap_or(""));
+            let upstream = fields.next().unwrap_or("");
+            let track = fields.next().unwrap_or("");
+
+            match (head == "*", upstream.is_empty()) {
+                (true, _) => format!("{}* {name}{} {upstream} {track}", style::Bold, style::Reset),
+                (false, true) => format!("  {name}"),
+                (false, false) => format!("  {name} {upstream} {track}"),
+            }
+        })
+        .collect())
+}
+
+// `stash@{0}: <subject>`
+fn stashes(path: &Path) -> io::Result<Vec<String>> {
+    let output = util::git(path)
+        .args(["stash", "list", "--format=%gd: %gs"])
+        .output()?;
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_owned)
+        .collect())
+}
+
+fn counts<'a>(counts: impl IntoIterator<Item = (&'a str, usize)>) -> String {
+    let counts: Vec<_> = counts
+        .into_iter()
+        .filter(|&(_, n)| n != 0)
+        .map(|(name, n)| format!("{name} {n}"))
+        .collect();
+
+    if counts.is_empty() {
+        "-".to_owned()
+    } else {
+        counts.join(", ")
+    }
+}
+
+fn heading(lines: &mut Vec<String>, title: &str) {
+    lines.push(String::new());
+    lines.push(format!(
+        "{}{title}{}",
+        color::Fg(color::LightBlack),
+        style::Reset
+    ));
+}
+
+// everything shown, one entry per line
+fn dashboard(path: &Path, config: &config::Config, options: &repo::RenderOptions) -> Vec<String> {
+    let snapshot = match get_snapshot(path, config) {
+        Ok(snapshot) => snapshot,
+        Err(err) => return vec![format!("{}: {err}", crate::output::error(options))],
+    };
+
+    let mut lines = vec![snapshot.render(options)];
+
+    if let Some(operation) = &snapshot.operation {
+        heading(&mut lines, "operation");
+        lines.push(match operation.kind {
+            repo::ConflictKind::Merge => {
+                format!("merging {} into {}", operation.target, operation.source)
+            }
+            repo::ConflictKind::Rebase => {
+                format!("rebasing {} onto {}", operation.target, operation.source)
+            }
+            repo::ConflictKind::CherryPick => {
+                format!("picking {} onto {}", operation.target, operation.source)
+            }
+            repo::ConflictKind::Revert => {
+                format!("reverting {} on {}", operation.target, operation.source)
+            }
+            repo::ConflictKind::Other => format!("conflicts on {}", operation.source),
+        });
+    }
+
+    heading(&mut lines, "changes");
+    let changes = |changes: &repo::Changes| counts(changes.iter().map(|(c, &n)| (c.name(), n)));
+    lines.push(format!("index        {}", changes(&snapshot.index)));
+    lines.push(format!("working tree {}", changes(&snapshot.working_tree)));
+    lines.push(format!(
+        "submodules   {}",
+        counts(snapshot.submodules.counts())
+    ));
+    lines.push(format!(
+        "conflicts    {}",
+        counts(snapshot.conflicts.counts())
+    ));
+
+    heading(&mut lines, "branches");
+    lines.extend(branches(path).unwrap_or_default());
+
+    heading(&mut lines, "stash");
+    match stashes(path).unwrap_or_default() {
+        stashes if stashes.is_empty() => lines.push("-".to_owned()),
+        stashes => lines.extend(stashes),
+    }
+
+    lines
+}
+
+fn draw(out: &mut impl Write, lines: &[String], git_dir: Option<&PathBuf>) -> io::Result<()> {
+    // some terminals report no size at all
+    let (_, height) = termion::terminal_size()
+        .ok()
+        .filter(|&(_, height)| height != 0)
+        .unwrap_or((80, 24));
+
+    write!(out, "{}", clear::All)?;
+    for (row, line) in lines
+        .iter()
+        .take(height.saturating_sub(2) as usize)
+        .enumerate()
+    {
+        write!(out, "{}{line}", cursor::Goto(1, row as u16 + 1))?;
+    }
+
+    let watching = git_dir.map_or_else(String::new, |dir| format!(", watching {}", dir.display()));
+    write!(
+        out,
+        "{}{}q quit, r refresh{watching}{}",
+        cursor::Goto(1, height),
+        color::Fg(color::LightBlack),
+        style::Reset
+    )?;
+
+    out.flush()
+}
+
+/// A read-only dashboard of the repo at `path` on the alternate screen, redrawn whenever the
+/// git dir changes and every few seconds for changes to the working tree.
+pub fn run(path: &Path, config: &config::Config, options: &repo::RenderOptions) -> io::Result<()> {
+    let git_dir = util::find_git_dir(path);
+
+    let mut stdin = termion::async_stdin();
+    let mut keys = [0; 16];
+    let mut out = AlternateScreen::from(io::stdout().into_raw_mode()?);
+    write!(out, "{}", cursor::Hide)?;
+
+    let mut seen = None;
+    let mut drawn_at = Instant::now();
+
+    let result = loop {
+        let current = git_dir.as_deref().map(fingerprint);
+        if seen.as_ref() != Some(&current) || drawn_at.elapsed() >= REFRESH {
+            if let Err(err) = draw(
+                &mut out,
+                &dashboard(path, config, options),
+                git_dir.as_ref(),
+            ) {
+                break Err(err);
+            }
+
+            seen = Some(current);
+            drawn_at = Instant::now();
+        }
+
+        // nothing read is not an error, the reader does not block
+        let keys = match stdin.read(&mut keys) {
+            Ok(n) => &keys[..n],
+            Err(err) => break Err(err),
+        };
+
+        // `q` or ctrl-c
+        if keys.iter().any(|&key| key == b'q' || key == 0x03) {
+            break Ok(());
+        }
+
+        if keys.contains(&b'r') {
+            seen = None;
+        } else {
+            thread::sleep(POLL);
+        }
+    };
+
+    write!(out, "{}", cursor::Show)?;
+    out.flush()?;
+    result
+}
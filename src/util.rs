@@ -1,9 +1,13 @@
-// use crate::repo;
+#[cfg(feature = "cli")]
+use crate::{cache, repo};
+#[cfg(feature = "cli")]
+use std::process::Command;
 use std::{
     borrow::Cow,
-    fs::File,
-    io::{self, Read},
-    path::Path,
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 pub fn path_rel_to_abs<'p>(pwd: &'p Path, arg_path: Option<&'p Path>) -> Cow<'p, Path> {
@@ -21,23 +25,647 @@ pub fn path_rel_to_abs<'p>(pwd: &'p Path, arg_path: Option<&'p Path>) -> Cow<'p,
     }
 }
 
+// `--dir-fd`, paths below this resolve against the open directory wherever it is now, like `openat`
+#[cfg(target_os = "linux")]
+const FD_DIR: &str = "/proc/self/fd";
+#[cfg(all(unix, not(target_os = "linux")))]
+const FD_DIR: &str = "/dev/fd";
+
+/// A path to the directory open as `fd`, for working in it without going through its name.
+#[cfg(unix)]
+pub fn dir_fd_path(fd: u32) -> io::Result<PathBuf> {
+    let path = Path::new(FD_DIR).join(fd.to_string());
+    if !fs::metadata(&path)?.is_dir() {
+        return Err(io::Error::other(format!("fd {fd} is not a directory")));
+    }
+
+    Ok(path)
+}
+
+#[cfg(not(unix))]
+pub fn dir_fd_path(_fd: u32) -> io::Result<PathBuf> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "`--dir-fd` is only supported on unix",
+    ))
+}
+
+// whether `path` goes through an fd opened by `--dir-fd`
+fn is_dir_fd_path(path: &Path) -> bool {
+    #[cfg(unix)]
+    return path.starts_with(FD_DIR);
+
+    #[cfg(not(unix))]
+    return {
+        let _ = path;
+        false
+    };
+}
+
+/// `path` by its current name if it goes through an fd, for keys which must not depend on the fd
+/// number.
+pub fn stable_path(path: &Path) -> Cow<'_, Path> {
+    match is_dir_fd_path(path) {
+        true => fs::canonicalize(path).map_or(Cow::Borrowed(path), Cow::Owned),
+        false => Cow::Borrowed(path),
+    }
+}
+
+// parents by `..` for fd paths, their names would leave the open directory
+fn ancestors(path: &Path) -> Vec<PathBuf> {
+    if !is_dir_fd_path(path) {
+        return path.ancestors().map(Path::to_path_buf).collect();
+    }
+
+    let mut ancestors = vec![path.to_path_buf()];
+    let mut dir = path.to_path_buf();
+    while fs::canonicalize(&dir).is_ok_and(|dir| dir.parent().is_some()) {
+        dir.push("..");
+        ancestors.push(dir.clone());
+    }
+
+    ancestors
+}
+
+// <prefix> <XY> <sub> <...>, `None` if the line is cut short or the fields are not as wide as git
+// writes them, the line may come from anywhere, see `parse_status`
+fn parse_xy_fields<'l>(line: &'l str, prefix: &str) -> Option<(char, char, &'l str)> {
+    let mut fields = line.strip_prefix(prefix)?.split(' ');
+    let mut xy = fields.next()?.chars();
+    let sub = fields.next().filter(|sub| sub.len() == 4)?;
+
+    match (xy.next(), xy.next(), xy.next()) {
+        (Some(x), Some(y), None) => Some((x, y, sub)),
+        _ => None,
+    }
+}
+
 // ignore non `N...` (submodules)
 // <prefix> <XY> N... <...>
 pub fn parse_xy_line(line: &str, prefix: &str) -> Option<(char, char)> {
-    line.strip_prefix(prefix)
-        .and_then(|rest| (&rest[3..7] == "N...").then(|| &rest[..2]))
-        .map(|xy| (xy.as_bytes()[0] as char, xy.as_bytes()[1] as char))
+    parse_xy_fields(line, prefix)
+        .filter(|&(_, _, sub)| sub == "N...")
+        .map(|(x, y, _)| (x, y))
+}
+
+// only submodules, see above
+// <prefix> <XY> S<c><m><u> <...>
+pub fn parse_submodule_line<'l>(line: &'l str, prefix: &str) -> Option<(char, char, &'l str)> {
+    parse_xy_fields(line, prefix).filter(|(_, _, sub)| sub.starts_with('S'))
+}
+
+#[cfg(feature = "cli")]
+// user config must not change the shape of the output we parse, anything which is a matter of
+// taste rather than format (e.g. `status.showUntrackedFiles`) is left alone
+const GIT_OVERRIDES: &[&str] = &[
+    "core.quotePath=false",
+    "status.relativePaths=false",
+    "status.aheadBehind=true",
+    "column.ui=never",
+    "color.ui=never",
+    "log.showSignature=false",
+];
+
+#[cfg(feature = "cli")]
+/// `git` run in `path` with [`GIT_OVERRIDES`] applied, never taking optional locks so it does not
+/// race with whatever else is running git in the repo.
+pub fn git(path: &Path) -> Command {
+    let mut command = Command::new("git");
+    command.arg("--no-optional-locks");
+    for config in GIT_OVERRIDES {
+        command.args(["-c", config]);
+    }
+    command.current_dir(path);
+    command
 }
 
+// without running git, `.git` is either the git dir or a file containing `gitdir: <path>` for
+// linked worktrees and submodules
+pub fn find_git_dir(path: &Path) -> Option<PathBuf> {
+    ancestors(path).into_iter().find_map(|dir| {
+        let dot_git = dir.join(".git");
+        if dot_git.is_dir() {
+            return Some(dot_git);
+        }
+
+        let content = fs::read_to_string(&dot_git).ok()?;
+        let git_dir = content.strip_prefix("gitdir: ")?.trim_end();
+        Some(dir.join(git_dir))
+    })
+}
+
+// held by another git process, e.g. an editor integration refreshing the index
+pub fn is_index_locked(path: &Path) -> bool {
+    find_git_dir(path).is_some_and(|git_dir| git_dir.join("index.lock").exists())
+}
+
+// first line, e.g. an octopus MERGE_HEAD has one per merged commit, the newline may be missing
 pub fn try_get_file_content(path: impl AsRef<Path>) -> io::Result<Option<String>> {
     match File::open(path) {
         Ok(mut file) => {
             let mut content = String::new();
             file.read_to_string(&mut content)?;
-            assert_eq!(content.pop(), Some('\n'));
-            Ok(Some(content))
+            Ok(Some(content.lines().next().unwrap_or_default().to_owned()))
         }
         Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
         Err(err) => Err(err),
     }
 }
+
+// rebase-merge (interactive/merge backend) or rebase-apply (apply backend), `None` if no rebase is
+// in progress
+// head-name    refs/heads/<branch> | detached HEAD
+// onto         <commit>
+// applying     only present if rebase-apply is used by `git am` instead
+pub fn try_get_rebase_state(
+    git_dir: &Path,
+) -> io::Result<Option<(Option<String>, Option<String>)>> {
+    for dir in ["rebase-merge", "rebase-apply"] {
+        let dir = git_dir.join(dir);
+        if !dir.is_dir() || dir.join("applying").exists() {
+            continue;
+        }
+
+        let head_name = try_get_file_content(dir.join("head-name"))?
+            .filter(|name| name.starts_with("refs/heads/"));
+        let onto = try_get_file_content(dir.join("onto"))?;
+
+        return Ok(Some((head_name, onto)));
+    }
+
+    Ok(None)
+}
+
+#[cfg(feature = "cli")]
+// `--points-at` peels annotated tags, so this also finds tags whose object id is not the commit
+// itself, prefer the highest version if there are multiple
+pub fn try_get_tag(path: &Path) -> io::Result<Option<String>> {
+    let output = git(path)
+        .args([
+            "for-each-ref",
+            "refs/tags",
+            "--points-at=HEAD",
+            "--sort=-v:refname",
+            "--count=1",
+            "--format=%(refname:strip=2)",
+        ])
+        .output()?;
+
+    let tag = String::from_utf8_lossy(&output.stdout);
+    Ok(tag
+        .lines()
+        .next()
+        .filter(|_| output.status.success())
+        .map(str::to_owned))
+}
+
+#[cfg(feature = "cli")]
+// <tag>-<offset>-g<abbrev>
+// `--long` always includes offset and hash, `rsplit` because tags may contain `-`
+pub fn try_get_describe(path: &Path, commit: &str) -> io::Result<Option<repo::Describe>> {
+    let output = git(path)
+        .args(["describe", "--tags", "--long", commit])
+        .output()?;
+
+    // no tags reachable
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let describe = String::from_utf8_lossy(&output.stdout);
+    let mut parts = describe.trim_end().rsplitn(3, '-');
+
+    Ok(match (parts.next(), parts.next(), parts.next()) {
+        (Some(_), Some(offset), Some(tag)) => offset.parse().ok().map(|offset| {
+            repo::Describe::new(tag.to_owned(), offset, repo::Commit::new(commit.to_owned()))
+        }),
+        _ => None,
+    })
+}
+
+#[cfg(feature = "cli")]
+pub fn try_get_toplevel(path: &Path) -> io::Result<Option<PathBuf>> {
+    let output = git(path).args(["rev-parse", "--show-toplevel"]).output()?;
+
+    Ok(output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim_end().into()))
+}
+
+#[cfg(feature = "cli")]
+pub fn is_inside_git_dir(path: &Path) -> io::Result<bool> {
+    Ok(try_get_git_output(path, &["rev-parse", "--is-inside-git-dir"])?.as_deref() == Some("true"))
+}
+
+#[cfg(feature = "cli")]
+// the git dir is `.git` for regular repos and `.git/worktrees/<name>` for linked worktrees, the
+// common dir is always `.git`
+// without `--path-format` the common dir may be relative to `path`
+// with `--dir-fd` both are kept relative to the fd, git only knows the current name of the dir
+pub fn try_get_git_dirs(path: &Path) -> io::Result<Option<(PathBuf, PathBuf)>> {
+    let anchored = is_dir_fd_path(path);
+    let output = git(path)
+        .arg("rev-parse")
+        .args(match anchored {
+            true => ["--path-format=relative", "--git-dir", "--git-common-dir"],
+            false => [
+                "--path-format=absolute",
+                "--absolute-git-dir",
+                "--git-common-dir",
+            ],
+        })
+        .output()?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let lines = String::from_utf8_lossy(&output.stdout);
+    let mut lines = lines.lines();
+
+    Ok(match (lines.next(), lines.next()) {
+        (Some(git_dir), Some(common_dir)) => Some((path.join(git_dir), path.join(common_dir))),
+        _ => None,
+    })
+}
+
+// reflogs only ever grow, the last few entries are enough to find the latest checkout
+const REFLOG_TAIL: u64 = 8 * 1024;
+
+// <old> <new> <name> <<email>> <unix secs> <tz>\tcheckout: moving from <a> to <b>
+pub fn try_get_last_checkout(git_dir: &Path) -> io::Result<Option<u64>> {
+    let mut file = match File::open(git_dir.join("logs/HEAD")) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+
+    let len = file.metadata()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(REFLOG_TAIL)))?;
+
+    let mut tail = vec![];
+    file.read_to_end(&mut tail)?;
+
+    Ok(String::from_utf8_lossy(&tail)
+        .lines()
+        .rev()
+        .filter_map(|line| line.split_once('\t'))
+        .find(|(_, message)| message.starts_with("checkout: "))
+        .and_then(|(entry, _)| entry.rsplit(' ').nth(1)?.parse().ok()))
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+// the largest unit only, `3h` rather than `3h12m`
+pub fn fmt_age(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{secs}s"),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        86400..=31535999 => format!("{}d", secs / 86400),
+        _ => format!("{}y", secs / 31536000),
+    }
+}
+
+#[cfg(feature = "cli")]
+fn try_get_git_output(path: &Path, args: &[&str]) -> io::Result<Option<String>> {
+    let output = git(path).args(args).output()?;
+
+    Ok(output.status.success().then(|| {
+        String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_owned()
+    }))
+}
+
+#[cfg(feature = "cli")]
+// branches under `pattern` with commits on top of HEAD, cached per HEAD and branch tips as
+// `--contains` walks history while listing the tips does not
+pub fn try_get_stacked(path: &Path, head: &str, pattern: &str) -> io::Result<Option<usize>> {
+    let format = "--format=%(objectname)";
+    let Some(tips) = try_get_git_output(path, &["for-each-ref", format, pattern])? else {
+        return Ok(None);
+    };
+
+    let key = cache::hash_key(format!("{head}\0{pattern}\0{tips}").as_bytes());
+    let count = cache::get_or_insert_with("stacked-branches", &key, || {
+        let contains = ["for-each-ref", format, "--contains", head, pattern];
+        Ok::<_, io::Error>(try_get_git_output(path, &contains)?.map(|tips| {
+            // branches at HEAD itself, including the current one, are not stacked on it
+            tips.lines().filter(|&tip| tip != head).count().to_string()
+        }))
+    })?;
+
+    Ok(count.and_then(|count| count.parse().ok()))
+}
+
+#[cfg(feature = "cli")]
+// `git config -z` listing of the `prompt.*` settings, local ones last so they win
+pub fn try_get_prompt_config(path: &Path) -> io::Result<Option<String>> {
+    let output = git(path)
+        .args(["config", "-z", "--get-regexp", r"^prompt\."])
+        .output()?;
+
+    // nothing set is exit code 1
+    Ok(output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
+#[cfg(feature = "cli")]
+// as long as `core.abbrev` says, longer where needed to be unique
+pub fn try_get_abbrev(path: &Path, commit: &str) -> io::Result<Option<String>> {
+    try_get_git_output(path, &["rev-parse", "--short", commit])
+}
+
+#[cfg(feature = "cli")]
+// with `insteadOf` applied, as git would fetch from it
+pub fn try_get_remote_url(path: &Path, remote: &str) -> io::Result<Option<String>> {
+    try_get_git_output(path, &["remote", "get-url", remote])
+}
+
+#[cfg(feature = "cli")]
+// the configured base or whatever the default branch of `origin` is
+pub fn try_get_base(path: &Path, base: Option<&str>) -> io::Result<Option<String>> {
+    let base = format!("{}^{{commit}}", base.unwrap_or("refs/remotes/origin/HEAD"));
+    try_get_git_output(path, &["rev-parse", "--verify", "--quiet", &base])
+}
+
+#[cfg(feature = "cli")]
+pub fn try_get_merge_base(path: &Path, a: &str, b: &str) -> io::Result<Option<String>> {
+    try_get_git_output(path, &["merge-base", a, b])
+}
+
+#[cfg(feature = "cli")]
+// committer date, this is what changes on rebase
+pub fn try_get_commit_time(path: &Path, commit: &str) -> io::Result<Option<u64>> {
+    Ok(
+        try_get_git_output(path, &["show", "--no-patch", "--format=%ct", commit])?
+            .and_then(|time| time.parse().ok()),
+    )
+}
+
+#[cfg(feature = "cli")]
+// `WIP on <branch>: <commit> <subject>` | `On <branch>: <message>`, the branch is `(no branch)` if
+// detached
+pub fn try_get_stash_subject(path: &Path) -> io::Result<Option<(String, String)>> {
+    let Some(line) = try_get_git_output(path, &["stash", "list", "-1", "--format=%gs"])? else {
+        return Ok(None);
+    };
+
+    let (wip, rest) = match line.strip_prefix("WIP on ") {
+        Some(rest) => (true, rest),
+        None => (false, line.strip_prefix("On ").unwrap_or(&line)),
+    };
+
+    Ok(rest.split_once(": ").map(|(branch, subject)| {
+        let subject = match wip {
+            true => subject
+                .split_once(' ')
+                .map_or(subject, |(_, subject)| subject),
+            false => subject,
+        };
+
+        (branch.to_owned(), subject.to_owned())
+    }))
+}
+
+#[cfg(feature = "cli")]
+// ` 2 files changed, 120 insertions(+), 34 deletions(-)`, either count is left out if zero and
+// the whole line if nothing changed
+pub fn try_get_diffstat(path: &Path, cached: bool) -> io::Result<Option<repo::DiffStat>> {
+    let args: &[&str] = match cached {
+        true => &["diff", "--shortstat", "--cached"],
+        false => &["diff", "--shortstat"],
+    };
+
+    Ok(try_get_git_output(path, args)?.map(|line| {
+        // `<n> insertion(s)(+)`
+        let count = |suffix: &str| {
+            line.split(", ")
+                .filter(|part| part.ends_with(suffix))
+                .find_map(|part| part.trim().split(' ').next()?.parse().ok())
+                .unwrap_or(0)
+        };
+
+        repo::DiffStat {
+            insertions: count("(+)"),
+            deletions: count("(-)"),
+        }
+    }))
+}
+
+#[cfg(feature = "cli")]
+// `<tag> <path>`, `S` for skip-worktree and lowercase tags for assume-unchanged
+pub fn try_get_hidden_count(path: &Path) -> io::Result<Option<usize>> {
+    let output = git(path).args(["ls-files", "-v", "-z"]).output()?;
+
+    Ok(output.status.success().then(|| {
+        output
+            .stdout
+            .split(|&byte| byte == 0)
+            .filter(|entry| matches!(entry.first(), Some(b'S' | b'a'..=b'z')))
+            .count()
+    }))
+}
+
+// unix secs of the last fetch, linked worktrees may have fetched on their own or through the main
+// one, a fresh clone has not fetched at all
+pub fn try_get_last_fetch(git_dir: &Path, common_dir: &Path) -> Option<u64> {
+    [git_dir, common_dir]
+        .iter()
+        .filter_map(|dir| fs::metadata(dir.join("FETCH_HEAD")).ok()?.modified().ok())
+        .filter_map(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|since| since.as_secs())
+        .max()
+}
+
+// East Asian wide and fullwidth ranges and emoji drawn as such, a subset of Unicode's
+// `EastAsianWidth.txt` covering what shows up in branch names, subjects and the glyphs
+const WIDE: &[(char, char)] = &[
+    ('\u{1100}', '\u{115f}'),
+    ('\u{231a}', '\u{231b}'),
+    ('\u{23e9}', '\u{23ec}'),
+    ('\u{23f0}', '\u{23f0}'),
+    ('\u{23f3}', '\u{23f3}'),
+    ('\u{25fd}', '\u{25fe}'),
+    ('\u{2614}', '\u{2615}'),
+    ('\u{2648}', '\u{2653}'),
+    ('\u{267f}', '\u{267f}'),
+    ('\u{2693}', '\u{2693}'),
+    ('\u{26a1}', '\u{26a1}'),
+    ('\u{26aa}', '\u{26ab}'),
+    ('\u{26bd}', '\u{26be}'),
+    ('\u{26c4}', '\u{26c5}'),
+    ('\u{26ce}', '\u{26ce}'),
+    ('\u{26d4}', '\u{26d4}'),
+    ('\u{26ea}', '\u{26ea}'),
+    ('\u{26f2}', '\u{26f3}'),
+    ('\u{26f5}', '\u{26f5}'),
+    ('\u{26fa}', '\u{26fa}'),
+    ('\u{26fd}', '\u{26fd}'),
+    ('\u{2705}', '\u{2705}'),
+    ('\u{270a}', '\u{270b}'),
+    ('\u{2728}', '\u{2728}'),
+    ('\u{274c}', '\u{274c}'),
+    ('\u{274e}', '\u{274e}'),
+    ('\u{2753}', '\u{2755}'),
+    ('\u{2757}', '\u{2757}'),
+    ('\u{2795}', '\u{2797}'),
+    ('\u{27b0}', '\u{27b0}'),
+    ('\u{27bf}', '\u{27bf}'),
+    ('\u{2b1b}', '\u{2b1c}'),
+    ('\u{2b50}', '\u{2b50}'),
+    ('\u{2b55}', '\u{2b55}'),
+    ('\u{2e80}', '\u{303e}'),
+    ('\u{3041}', '\u{33ff}'),
+    ('\u{3400}', '\u{4dbf}'),
+    ('\u{4e00}', '\u{9fff}'),
+    ('\u{a000}', '\u{a4cf}'),
+    ('\u{ac00}', '\u{d7a3}'),
+    ('\u{f900}', '\u{faff}'),
+    ('\u{fe30}', '\u{fe4f}'),
+    ('\u{ff00}', '\u{ff60}'),
+    ('\u{ffe0}', '\u{ffe6}'),
+    ('\u{1f300}', '\u{1f64f}'),
+    ('\u{1f680}', '\u{1f6ff}'),
+    ('\u{1f900}', '\u{1f9ff}'),
+    ('\u{20000}', '\u{3fffd}'),
+];
+
+/// The terminal cells `c` takes, 0 for combining marks and other zero width chars, 2 for wide
+/// ones, e.g. CJK and emoji.
+pub fn char_width(c: char) -> usize {
+    match c {
+        '\u{0300}'..='\u{036f}'
+        | '\u{200b}'..='\u{200f}'
+        | '\u{20d0}'..='\u{20ff}'
+        | '\u{fe00}'..='\u{fe0f}' => 0,
+        c if c.is_control() => 0,
+        c if WIDE.iter().any(|&(lo, hi)| (lo..=hi).contains(&c)) => 2,
+        _ => 1,
+    }
+}
+
+/// The byte offset of char `n` of `s`, its length if there are fewer.
+pub fn char_offset(s: &str, n: usize) -> usize {
+    s.char_indices().nth(n).map_or(s.len(), |(i, _)| i)
+}
+
+/// `s` cut to `len` chars, `ellipsis`, e.g. `…`, among them if anything was cut, without it if
+/// not even the ellipsis fits.
+pub fn truncate<'s>(s: &'s str, len: usize, ellipsis: &str) -> Cow<'s, str> {
+    if s.chars().nth(len).is_none() {
+        return Cow::Borrowed(s);
+    }
+
+    match len.checked_sub(ellipsis.chars().count()) {
+        Some(keep) => Cow::Owned(format!("{}{ellipsis}", &s[..char_offset(s, keep)])),
+        None => Cow::Borrowed(&s[..char_offset(s, len)]),
+    }
+}
+
+/// `s` cut to `len` chars by leaving out its middle, marked with `ellipsis` as [`truncate`] does.
+pub fn truncate_middle<'s>(s: &'s str, len: usize, ellipsis: &str) -> Cow<'s, str> {
+    let count = s.chars().count();
+    if count <= len {
+        return Cow::Borrowed(s);
+    }
+
+    let Some(keep) = len.checked_sub(ellipsis.chars().count()) else {
+        return Cow::Borrowed(&s[..char_offset(s, len)]);
+    };
+
+    // the start gets the odd one out
+    let head = &s[..char_offset(s, keep.div_ceil(2))];
+    let tail = &s[char_offset(s, count - keep / 2)..];
+    Cow::Owned(format!("{head}{ellipsis}{tail}"))
+}
+
+#[cfg(feature = "cli")]
+// the first line of the commit message, cached per commit like its time
+pub fn try_get_subject(path: &Path, head: &str) -> io::Result<Option<String>> {
+    cache::get_or_insert_with("commit-subject", head, || {
+        try_get_git_output(path, &["show", "--no-patch", "--format=%s", head])
+    })
+}
+
+#[cfg(feature = "cli")]
+// the `%G?` signature status of a commit, e.g. `G` for good or `N` for none, cached per commit
+// unless the key was missing, it may well be imported later
+pub fn try_get_signature(path: &Path, head: &str) -> io::Result<Option<char>> {
+    if let Some(status) = cache::get("commit-signature", head) {
+        return Ok(status.chars().next());
+    }
+
+    let status = try_get_git_output(path, &["show", "--no-patch", "--format=%G?", head])?;
+    if let Some(status) = status.as_deref().filter(|&status| status != "E") {
+        let _ = cache::set("commit-signature", head, status);
+    }
+
+    Ok(status.and_then(|status| status.chars().next()))
+}
+
+#[cfg(feature = "cli")]
+// time since HEAD was committed, cached per commit as that can't change
+pub fn try_get_head_age(path: &Path, head: &str) -> io::Result<Option<u64>> {
+    let time = cache::get_or_insert_with("commit-time", head, || {
+        Ok::<_, io::Error>(try_get_commit_time(path, head)?.map(|time| time.to_string()))
+    })?;
+
+    Ok(time
+        .and_then(|time| time.parse::<u64>().ok())
+        .map(|time| unix_now().saturating_sub(time)))
+}
+
+#[cfg(feature = "cli")]
+// time since the merge base with `base`, nothing if HEAD is not ahead of it
+// cached per (head, base), the merge base can't change without either of them changing
+pub fn try_get_branch_age(path: &Path, head: &str, base: &str) -> io::Result<Option<u64>> {
+    let time = cache::get_or_insert_with("branch-age", &format!("{head}-{base}"), || {
+        // cache the absence of a merge base as empty
+        Ok::<_, io::Error>(Some(match try_get_merge_base(path, head, base)? {
+            Some(merge_base) if merge_base != head => try_get_commit_time(path, &merge_base)?
+                .map(|time| time.to_string())
+                .unwrap_or_default(),
+            _ => String::new(),
+        }))
+    })?;
+
+    Ok(time
+        .and_then(|time| time.parse::<u64>().ok())
+        .map(|time| unix_now().saturating_sub(time)))
+}
+
+#[cfg(feature = "cli")]
+// the short name of `rev` and ahead/behind of HEAD relative to it, nothing if it does not resolve,
+// e.g. `@{push}` if that was never fetched
+pub fn try_get_comparison(path: &Path, rev: &str) -> io::Result<Option<(String, usize, usize)>> {
+    let Some(name) = try_get_git_output(path, &["rev-parse", "--abbrev-ref", rev])? else {
+        return Ok(None);
+    };
+
+    let range = format!("HEAD...{rev}");
+    let counts = try_get_git_output(path, &["rev-list", "--left-right", "--count", &range])?;
+    Ok(counts.and_then(|counts| {
+        let (ahead, behind) = counts.split_once('\t')?;
+        Some((name, ahead.parse().ok()?, behind.parse().ok()?))
+    }))
+}
+
+#[cfg(feature = "cli")]
+// commits reachable from HEAD but not from `base`, cached like the branch age
+pub fn try_get_branch_commits(path: &Path, head: &str, base: &str) -> io::Result<Option<usize>> {
+    let count = cache::get_or_insert_with("branch-commits", &format!("{head}-{base}"), || {
+        try_get_git_output(path, &["rev-list", "--count", &format!("{base}..{head}")])
+    })?;
+
+    Ok(count.and_then(|count| count.parse().ok()))
+}
@@ -0,0 +1,140 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+};
+
+use crate::{config, dirs, repo};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Summary {
+    repos: usize,
+    dirty: usize,
+    ahead: usize,
+    behind: usize,
+    conflicted: usize,
+    errors: usize,
+}
+
+impl Summary {
+    fn add(&mut self, prompt: Option<&repo::Prompt>) {
+        self.repos += 1;
+
+        let Some(prompt) = prompt else {
+            self.errors += 1;
+            return;
+        };
+
+        if prompt.is_dirty() {
+            self.dirty += 1;
+        }
+
+        if let Some((ahead, behind)) = prompt
+            .branch()
+            .and_then(repo::Branch::divergence)
+            .map(repo::Divergence::ahead_behind)
+        {
+            self.ahead += (ahead != 0) as usize;
+            self.behind += (behind != 0) as usize;
+        }
+
+        if prompt.conflicts() != 0 {
+            self.conflicted += 1;
+        }
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use termion::{color, style};
+
+        let parts = [
+            (self.conflicted, "conflicted", color::Red.fg_str()),
+            (self.errors, "failed", color::Red.fg_str()),
+            (self.behind, "behind", color::Red.fg_str()),
+            (self.dirty, "dirty", color::Yellow.fg_str()),
+            (self.ahead, "ahead", color::Blue.fg_str()),
+        ];
+
+        let mut is_first = true;
+        for (count, label, fg) in parts.into_iter().filter(|&(count, _, _)| count != 0) {
+            if !is_first {
+                f.write_str(", ")?;
+            }
+            is_first = false;
+
+            if f.alternate() {
+                write!(f, "{fg}{count} {label}{}", style::Reset)?;
+            } else {
+                write!(f, "{count} {label}")?;
+            }
+        }
+
+        if is_first {
+            if f.alternate() {
+                write!(
+                    f,
+                    "{}{} clean{}",
+                    color::Fg(color::Green),
+                    self.repos,
+                    style::Reset
+                )?;
+            } else {
+                write!(f, "{} clean", self.repos)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Resolves the repos of `workspace.<name>` and runs the status engine over all of them in
+/// parallel, `f` is called for each repo in the order they finish.
+pub fn scan(
+    name: &str,
+    config: &config::Config,
+    mut f: impl FnMut(&Path, Option<&repo::RepoSnapshot>),
+) -> Result<(), String> {
+    let repos: Vec<_> = config
+        .get_list(&format!("workspace.{name}"))
+        .ok_or_else(|| format!("unknown workspace `{name}`"))?
+        .iter()
+        .map(|value| match value {
+            config::Value::Str(path) => Ok(expand_home(path)),
+            value => Err(format!("workspace `{name}`: expected a path, got {value}")),
+        })
+        .collect::<Result<_, _>>()?;
+
+    thread::scope(|scope| {
+        let (tx, rx) = mpsc::channel();
+        for path in &repos {
+            let tx = tx.clone();
+            scope.spawn(move || {
+                let _ = tx.send((path, crate::get_snapshot(path, config).ok()));
+            });
+        }
+        drop(tx);
+
+        for (path, snapshot) in rx {
+            f(path, snapshot.as_ref());
+        }
+    });
+
+    Ok(())
+}
+
+pub fn summarize(name: &str, config: &config::Config) -> Result<Summary, String> {
+    let mut summary = Summary::default();
+    scan(name, config, |_, snapshot| {
+        summary.add(snapshot.map(repo::RepoSnapshot::prompt).as_ref())
+    })?;
+    Ok(summary)
+}
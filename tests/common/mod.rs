@@ -0,0 +1,143 @@
+//! A scratch dir with repos in it and the binary run against them, isolated from the user.
+#![allow(dead_code)]
+
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    process::{self, Command},
+};
+
+pub const ADVERSARIAL: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/adversarial.gitconfig"
+);
+
+pub struct Scratch(pub PathBuf);
+
+impl Scratch {
+    pub fn new(name: &str) -> Self {
+        let root = env::temp_dir().join(format!("epb-prompt-git-{}-{name}", process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        Self(root)
+    }
+
+    pub fn path(&self, rel: &str) -> PathBuf {
+        self.0.join(rel)
+    }
+
+    // isolated from the user and system config, `gitconfig` is the global config
+    pub fn command(&self, program: &str, dir: &Path, gitconfig: &str) -> Command {
+        let mut command = Command::new(program);
+        command
+            .current_dir(dir)
+            .env("HOME", &self.0)
+            .env("XDG_CONFIG_HOME", self.path("xdg/config"))
+            .env("XDG_CACHE_HOME", self.path("xdg/cache"))
+            .env("XDG_STATE_HOME", self.path("xdg/state"))
+            .env("GIT_CONFIG_GLOBAL", gitconfig)
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .env("GIT_AUTHOR_NAME", "epb")
+            .env("GIT_AUTHOR_EMAIL", "epb@example.com")
+            .env("GIT_COMMITTER_NAME", "epb")
+            .env("GIT_COMMITTER_EMAIL", "epb@example.com");
+        command
+    }
+
+    pub fn git(&self, dir: &str, args: &[&str]) {
+        let status = self
+            .command("git", &self.path(dir), "/dev/null")
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(status.status.success(), "git {args:?}: {status:?}");
+    }
+
+    pub fn write(&self, rel: &str, content: &str) {
+        let path = self.path(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    pub fn prompt(&self, dir: &str, gitconfig: &str, args: &[&str]) -> String {
+        self.prompt_with(dir, gitconfig, args, &[])
+    }
+
+    // `envs` on top of the isolated environment, e.g. another `PATH`
+    pub fn prompt_with(
+        &self,
+        dir: &str,
+        gitconfig: &str,
+        args: &[&str],
+        envs: &[(&str, OsString)],
+    ) -> String {
+        let output = self
+            .command(
+                env!("CARGO_BIN_EXE_epb-prompt-git"),
+                &self.path(dir),
+                gitconfig,
+            )
+            .args(args)
+            .envs(envs.iter().map(|(key, value)| (key, value)))
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        String::from_utf8(output.stdout).unwrap()
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+// one of each: ahead, behind, staged, unstaged, untracked with awkward names, stash
+pub fn busy_repo(name: &str) -> Scratch {
+    let scratch = Scratch::new(name);
+
+    scratch.git("", &["init", "-q", "-b", "main", "upstream"]);
+    scratch.write("upstream/tracked", "a\n");
+    scratch.write("upstream/sub/other", "a\n");
+    scratch.git("upstream", &["add", "."]);
+    scratch.git("upstream", &["commit", "-q", "-m", "initial"]);
+
+    scratch.git("", &["clone", "-q", "upstream", "work"]);
+
+    scratch.git(
+        "upstream",
+        &["commit", "-q", "--allow-empty", "-m", "theirs"],
+    );
+    scratch.git("work", &["commit", "-q", "--allow-empty", "-m", "ours"]);
+    scratch.git("work", &["fetch", "-q"]);
+
+    scratch.write("work/tracked", "stashed\n");
+    scratch.git("work", &["stash", "-q"]);
+
+    scratch.write("work/staged", "a\n");
+    scratch.git("work", &["add", "staged"]);
+    scratch.write("work/tracked", "b\n");
+    scratch.write("work/sub/other", "b\n");
+    scratch.write("work/sub/with space\tand tab", "a\n");
+    scratch.write("work/sub/ümläut", "a\n");
+
+    scratch
+}
+
+// both sides change the same line
+pub fn conflicting(name: &str) -> Scratch {
+    let scratch = Scratch::new(name);
+    scratch.git("", &["init", "-q", "-b", "main", "work"]);
+    scratch.write("work/file", "base\n");
+    scratch.git("work", &["add", "."]);
+    scratch.git("work", &["commit", "-q", "-m", "base"]);
+    scratch.git("work", &["checkout", "-q", "-b", "feature"]);
+    scratch.write("work/file", "feature\n");
+    scratch.git("work", &["commit", "-q", "-am", "feature"]);
+    scratch.git("work", &["checkout", "-q", "main"]);
+    scratch.write("work/file", "main\n");
+    scratch.git("work", &["commit", "-q", "-am", "main"]);
+    scratch
+}
@@ -0,0 +1,96 @@
+//! The prompt must not depend on user git config which only changes the shape of git output.
+
+mod common;
+
+use common::{busy_repo, ADVERSARIAL};
+
+#[test]
+fn tsv_is_unaffected() {
+    let scratch = busy_repo("tsv");
+
+    let expected = "main\torigin/main\t1\t1\t1\t2\t2\t0\t1\t\n";
+    assert_eq!(
+        scratch.prompt("work/sub", "/dev/null", &["--output=tsv"]),
+        expected
+    );
+    assert_eq!(
+        scratch.prompt("work/sub", ADVERSARIAL, &["--output=tsv"]),
+        expected
+    );
+}
+
+#[test]
+fn prompt_is_unaffected() {
+    let scratch = busy_repo("prompt");
+
+    assert_eq!(
+        scratch.prompt("work/sub", "/dev/null", &[]),
+        scratch.prompt("work/sub", ADVERSARIAL, &[])
+    );
+}
+
+#[test]
+fn detached_is_unaffected() {
+    let scratch = busy_repo("detached");
+    scratch.git("work", &["tag", "v1.9"]);
+    scratch.git("work", &["tag", "-a", "-m", "annotated", "v1.10"]);
+    scratch.git("work", &["checkout", "-q", "--detach"]);
+
+    let plain = scratch.prompt("work", "/dev/null", &[]);
+    assert!(plain.contains("v1.10"), "{plain:?}");
+    assert_eq!(plain, scratch.prompt("work", ADVERSARIAL, &[]));
+}
+
+#[test]
+fn repo_settings_apply() {
+    let scratch = busy_repo("settings");
+    scratch.git("work", &["config", "prompt.untracked", "no"]);
+
+    let expected = "main\torigin/main\t1\t1\t1\t2\t0\t0\t1\t\n";
+    assert_eq!(
+        scratch.prompt("work/sub", "/dev/null", &["--output=tsv"]),
+        expected
+    );
+
+    scratch.git("work", &["config", "prompt.disable", "yes"]);
+    assert_eq!(scratch.prompt("work/sub", "/dev/null", &[]), "");
+}
+
+#[test]
+fn repo_file_applies() {
+    let scratch = busy_repo("repo-file");
+    scratch.write("work/.git/epb-prompt.toml", "status.untracked = false\n");
+
+    let expected = "main\torigin/main\t1\t1\t1\t2\t0\t0\t1\t\n";
+    assert_eq!(
+        scratch.prompt("work/sub", "/dev/null", &["--output=tsv"]),
+        expected
+    );
+
+    // git config is more specific still
+    scratch.git("work", &["config", "prompt.untracked", "yes"]);
+    assert_ne!(
+        scratch.prompt("work/sub", "/dev/null", &["--output=tsv"]),
+        expected
+    );
+}
+
+#[test]
+fn disabled_by_env() {
+    let scratch = busy_repo("disabled");
+
+    let envs = [("EPB_PROMPT_DISABLE", "1".into())];
+    assert_eq!(scratch.prompt_with("work", "/dev/null", &[], &envs), "");
+}
+
+// like `NO_COLOR`, empty and `0` leave the prompt on
+#[test]
+fn enabled_by_env() {
+    let scratch = busy_repo("enabled");
+
+    for value in ["", "0"] {
+        let envs = [("EPB_PROMPT_DISABLE", value.into())];
+        let prompt = scratch.prompt_with("work", "/dev/null", &["--color=never"], &envs);
+        assert!(prompt.starts_with("main"), "{value:?}: {prompt:?}");
+    }
+}
@@ -0,0 +1,148 @@
+//! The prompt must not depend on the git release, porcelain output drifts between them.
+//!
+//! Only runs with `EPB_TEST_GIT_PATHS` set to git binaries separated like `PATH`, e.g.
+//! `EPB_TEST_GIT_PATHS=/opt/git-2.30/bin/git:/usr/bin/git cargo test --test git_versions`, each
+//! named `git` as it is found through `PATH` and without `GIT_EXEC_PATH` set. Fixtures are set up
+//! by the `git` on `PATH`.
+
+mod common;
+
+use std::{env, path::PathBuf};
+
+use common::{busy_repo, conflicting, Scratch};
+
+const OUTPUTS: [&[&str]; 4] = [
+    &[],
+    &["--output=tsv"],
+    &["--output=env"],
+    &["--output=symbol"],
+];
+
+fn git_paths() -> Option<Vec<PathBuf>> {
+    let paths = env::var_os("EPB_TEST_GIT_PATHS")?;
+    Some(env::split_paths(&paths).collect())
+}
+
+// each output with each git, the cache is per git so no release sees results of another
+fn assert_identical(scratch: &Scratch, dir: &str) {
+    let Some(gits) = git_paths() else {
+        eprintln!("EPB_TEST_GIT_PATHS is not set, skipped");
+        return;
+    };
+
+    let path = env::var_os("PATH").unwrap_or_default();
+    let runs: Vec<(PathBuf, Vec<String>)> = gits
+        .into_iter()
+        .enumerate()
+        .map(|(i, git)| {
+            let bin = git.parent().expect("git binary path").to_owned();
+            let path = env::join_paths([bin].into_iter().chain(env::split_paths(&path)))
+                .expect("git binary path without separators");
+
+            let envs = [
+                ("PATH", path),
+                (
+                    "XDG_CACHE_HOME",
+                    scratch.path(&format!("xdg/cache-{i}")).into(),
+                ),
+            ];
+            let outputs = OUTPUTS
+                .iter()
+                .map(|args| scratch.prompt_with(dir, "/dev/null", args, &envs))
+                .collect();
+            (git, outputs)
+        })
+        .collect();
+
+    let Some((reference, expected)) = runs.first() else {
+        return;
+    };
+
+    for (git, outputs) in &runs[1..] {
+        assert_eq!(
+            outputs,
+            expected,
+            "{} differs from {}",
+            git.display(),
+            reference.display()
+        );
+    }
+}
+
+#[test]
+fn busy() {
+    let scratch = busy_repo("versions-busy");
+    assert_identical(&scratch, "work/sub");
+}
+
+#[test]
+fn detached() {
+    let scratch = busy_repo("versions-detached");
+    scratch.git("work", &["tag", "v1.9"]);
+    scratch.git("work", &["tag", "-a", "-m", "annotated", "v1.10"]);
+    scratch.git("work", &["checkout", "-q", "--detach"]);
+    assert_identical(&scratch, "work");
+}
+
+#[test]
+fn headless() {
+    let scratch = Scratch::new("versions-headless");
+    scratch.git("", &["init", "-q", "-b", "main", "work"]);
+    scratch.write("work/staged", "a\n");
+    scratch.git("work", &["add", "staged"]);
+    scratch.write("work/untracked", "a\n");
+    assert_identical(&scratch, "work");
+}
+
+#[test]
+fn gone() {
+    let scratch = busy_repo("versions-gone");
+    scratch.git(
+        "work",
+        &["checkout", "-q", "-b", "feature", "--track", "origin/main"],
+    );
+    scratch.git("upstream", &["branch", "-q", "feature"]);
+    scratch.git("work", &["fetch", "-q"]);
+    scratch.git(
+        "work",
+        &["branch", "-q", "--set-upstream-to", "origin/feature"],
+    );
+    scratch.git("upstream", &["branch", "-q", "-D", "feature"]);
+    scratch.git("work", &["fetch", "-q", "--prune"]);
+    assert_identical(&scratch, "work");
+}
+
+#[test]
+fn merge_conflict() {
+    let scratch = conflicting("versions-merge");
+    let merge = scratch
+        .command("git", &scratch.path("work"), "/dev/null")
+        .args(["merge", "-q", "feature"])
+        .output()
+        .unwrap();
+    assert!(!merge.status.success(), "{merge:?}");
+    assert_identical(&scratch, "work");
+}
+
+#[test]
+fn rebase_conflict() {
+    let scratch = conflicting("versions-rebase");
+    let rebase = scratch
+        .command("git", &scratch.path("work"), "/dev/null")
+        .args(["rebase", "-q", "feature"])
+        .output()
+        .unwrap();
+    assert!(!rebase.status.success(), "{rebase:?}");
+    assert_identical(&scratch, "work");
+}
+
+#[test]
+fn worktree() {
+    let scratch = busy_repo("versions-worktree");
+    scratch.git(
+        "work",
+        &["worktree", "add", "-q", "-b", "linked", "../linked"],
+    );
+    scratch.write("linked/untracked", "a\n");
+    assert_identical(&scratch, "linked");
+}
@@ -0,0 +1,91 @@
+//! Operations in progress which stopped at conflicts, each needs a prompt and must not panic.
+
+mod common;
+
+use common::{conflicting, Scratch};
+
+// the command is expected to stop at a conflict
+fn conflict(scratch: &Scratch, args: &[&str]) {
+    let output = scratch
+        .command("git", &scratch.path("work"), "/dev/null")
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "{output:?}");
+}
+
+#[test]
+fn cherry_pick() {
+    let scratch = conflicting("cherry-pick");
+    conflict(&scratch, &["cherry-pick", "feature"]);
+
+    let tsv = scratch.prompt("work", "/dev/null", &["--output=tsv"]);
+    assert!(tsv.ends_with("\t1\t0\tcherry-pick\n"), "{tsv:?}");
+    let prompt = scratch.prompt("work", "/dev/null", &["--color=never"]);
+    assert!(prompt.starts_with("main <- feature"), "{prompt:?}");
+    let ps1 = scratch.prompt("work", "/dev/null", &["--output=ps1"]);
+    assert_eq!(ps1, " (main *+|CHERRY-PICKING)\n");
+}
+
+#[test]
+fn revert() {
+    let scratch = conflicting("revert");
+    scratch.write("work/file", "later\n");
+    scratch.git("work", &["commit", "-q", "-am", "later"]);
+    conflict(&scratch, &["revert", "--no-edit", "HEAD~1"]);
+
+    let tsv = scratch.prompt("work", "/dev/null", &["--output=tsv"]);
+    assert!(tsv.ends_with("\t1\t0\trevert\n"), "{tsv:?}");
+}
+
+// no operation state at all, only the conflicts
+#[test]
+fn stash_pop() {
+    let scratch = conflicting("stash-pop");
+    scratch.write("work/file", "stashed\n");
+    scratch.git("work", &["stash", "-q"]);
+    scratch.git("work", &["checkout", "-q", "feature"]);
+    conflict(&scratch, &["stash", "pop", "-q"]);
+
+    let tsv = scratch.prompt("work", "/dev/null", &["--output=tsv"]);
+    assert!(tsv.ends_with("\t1\t1\tconflict\n"), "{tsv:?}");
+    let prompt = scratch.prompt("work", "/dev/null", &["--color=never"]);
+    assert!(prompt.starts_with("feature ::"), "{prompt:?}");
+}
+
+// octopus merges list every merged commit, hand-written ones may lack the newline
+#[test]
+fn octopus_merge_head() {
+    let scratch = conflicting("octopus");
+    conflict(&scratch, &["merge", "-q", "feature"]);
+    let before = scratch.prompt("work", "/dev/null", &["--output=tsv"]);
+    let main = std::fs::read_to_string(scratch.path("work/.git/refs/heads/main")).unwrap();
+    let merge_head = std::fs::read_to_string(scratch.path("work/.git/MERGE_HEAD")).unwrap();
+    scratch.write(
+        "work/.git/MERGE_HEAD",
+        &format!("{}\n{}", merge_head.trim(), main.trim()),
+    );
+
+    for args in [&[][..], &["--sandbox"]] {
+        let prompt = scratch.prompt("work", "/dev/null", &[args, &["--color=never"]].concat());
+        assert!(
+            prompt.starts_with("main <- feature"),
+            "{args:?}: {prompt:?}"
+        );
+    }
+    assert_eq!(
+        scratch.prompt("work", "/dev/null", &["--output=tsv"]),
+        before
+    );
+}
+
+#[test]
+fn detached_merge() {
+    let scratch = conflicting("detached-merge");
+    scratch.git("work", &["checkout", "-q", "--detach"]);
+    conflict(&scratch, &["merge", "-q", "feature"]);
+
+    let prompt = scratch.prompt("work", "/dev/null", &["--color=never"]);
+    assert!(!prompt.starts_with("main"), "{prompt:?}");
+    assert!(prompt.contains(" <- feature"), "{prompt:?}");
+}
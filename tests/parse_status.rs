@@ -0,0 +1,90 @@
+//! `parse_status` takes porcelain from anywhere, e.g. stdin of `epb-prompt-render`, malformed lines
+//! are warnings and never a panic.
+
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+use epb_prompt_git::{config::Config, parse_status};
+
+const HEADER: &str = "# branch.oid abc\n# branch.head main\n";
+
+// `epb-prompt-render --no-color` with `stdin`, which must not fail
+fn render(stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_epb-prompt-render"))
+        .arg("--no-color")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "{output:?}");
+    output
+}
+
+fn warnings(lines: &str) -> Vec<String> {
+    parse_status(&format!("{HEADER}{lines}"), &Config::default()).warnings
+}
+
+#[test]
+fn short_lines() {
+    for line in [
+        "1", "1 ", "1 M", "1 .M", "1 .M N..", "2 R.", "u UU", "u UU N",
+    ] {
+        let warnings = warnings(&format!("{line}\n"));
+        assert_eq!(
+            warnings,
+            [format!("unknown status line `{line}`")],
+            "{line:?}"
+        );
+    }
+}
+
+#[test]
+fn non_ascii_lines() {
+    for line in [
+        "1 ü N... x",
+        "1 .M Nüü x",
+        "2 ¡R N... x",
+        "u Ü N... x",
+        "1 .M Sü.. x",
+    ] {
+        let warnings = warnings(&format!("{line}\n"));
+        assert_eq!(warnings.len(), 1, "{line:?}: {warnings:?}");
+    }
+}
+
+#[test]
+fn well_formed_lines() {
+    let snapshot = parse_status(
+        &format!("{HEADER}1 .M N... 100644 100644 100644 a b file\nu UU N... x\n"),
+        &Config::default(),
+    );
+    assert!(snapshot.warnings.is_empty(), "{:?}", snapshot.warnings);
+    assert_eq!(snapshot.conflicts.total(), 1);
+}
+
+#[test]
+fn render_survives_malformed_stdin() {
+    let output = render(&format!("{HEADER}1 M\n1 ü\n"));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("main"), "{stdout:?}");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("unknown status line `1 ü`"), "{stderr:?}");
+}
+
+// the hash is cut to 7 chars, not bytes
+#[test]
+fn render_survives_non_ascii_oid() {
+    let output = render("# branch.oid éééééééé\n# branch.head (detached)\n");
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "ééééééé\n");
+}
@@ -0,0 +1,492 @@
+//! Rendering must hold up for any snapshot and any way of rendering it.
+
+use epb_prompt_git::{
+    forge::Forge,
+    output,
+    repo::{
+        Background, Change, Changes, Comparison, ConflictKind, ConflictRef, Conflicts, DiffStat,
+        HintStyle, Markup, Operation, RenderOptions, RepoSnapshot, Segment, Segments, Submodules,
+        Template, Theme, Truncation, SUBJECT_LEN,
+    },
+    ticket::Ticket,
+};
+use proptest::{option, prelude::*, sample};
+
+// what `git check-ref-format` allows, roughly
+fn ref_name() -> impl Strategy<Value = String> {
+    "[^\\x00-\\x20\\x7f~^:?*\\[\\\\]{1,24}"
+}
+
+fn hash() -> impl Strategy<Value = String> {
+    "[0-9a-f]{40}"
+}
+
+// commit subjects and the output of `ci.command` and `pr.command` may contain anything
+fn text() -> impl Strategy<Value = String> {
+    ".{0,48}"
+}
+
+fn changes() -> impl Strategy<Value = Changes> {
+    proptest::collection::vec(0..3usize, 9).prop_map(|counts| {
+        let mut changes = Changes::new();
+        let kinds = [
+            Change::Add,
+            Change::Mod,
+            Change::Del,
+            Change::Ren,
+            Change::Cpy,
+            Change::Typ,
+            Change::Sub,
+            Change::Untracked,
+            Change::UntrackedDir,
+        ];
+        for (kind, count) in kinds.into_iter().zip(counts) {
+            changes[kind] = count;
+        }
+        changes
+    })
+}
+
+fn conflicts() -> impl Strategy<Value = Conflicts> {
+    proptest::collection::vec(sample::select(vec!["UU", "AA", "DD", "AU", "UD"]), 0..4).prop_map(
+        |xys| {
+            let mut conflicts = Conflicts::new();
+            for xy in xys {
+                let mut chars = xy.chars();
+                conflicts.add((chars.next().unwrap(), chars.next().unwrap()));
+            }
+            conflicts
+        },
+    )
+}
+
+fn submodules() -> impl Strategy<Value = Submodules> {
+    proptest::collection::vec(sample::select(vec!["SC..", "S.M.", "S..U", "SCMU"]), 0..3).prop_map(
+        |states| {
+            let mut submodules = Submodules::new();
+            for state in states {
+                submodules.add(state);
+            }
+            submodules
+        },
+    )
+}
+
+fn conflict_ref() -> impl Strategy<Value = ConflictRef> {
+    prop_oneof![
+        hash().prop_map(ConflictRef::commit),
+        ref_name().prop_map(ConflictRef::branch),
+    ]
+}
+
+fn operation() -> impl Strategy<Value = Operation> {
+    let kind = prop_oneof![
+        Just(ConflictKind::Merge),
+        Just(ConflictKind::Rebase),
+        Just(ConflictKind::CherryPick),
+        Just(ConflictKind::Revert),
+        Just(ConflictKind::Other),
+    ];
+    (kind, conflict_ref(), conflict_ref()).prop_map(|(kind, source, target)| Operation {
+        kind,
+        source,
+        target,
+    })
+}
+
+fn comparison() -> impl Strategy<Value = Comparison> {
+    (ref_name(), 0..3usize, 0..3usize).prop_map(|(name, ahead, behind)| Comparison {
+        name,
+        ahead,
+        behind,
+    })
+}
+
+fn ticket() -> impl Strategy<Value = Ticket> {
+    (
+        "[A-Z]{2,4}-[0-9]{1,4}",
+        "[a-z-]{0,12}",
+        option::of("https://[a-z./]{1,20}"),
+    )
+        .prop_map(|(id, rest, url)| Ticket { id, rest, url })
+}
+
+fn forge() -> impl Strategy<Value = Forge> {
+    (
+        sample::select(vec!["https://github.com/", "git@gitlab.com:"]),
+        ref_name(),
+    )
+        .prop_filter_map("not a forge", |(host, path)| {
+            Forge::from_url(&format!("{host}owner/{path}"))
+        })
+}
+
+fn snapshot() -> impl Strategy<Value = RepoSnapshot> {
+    let head = (
+        option::of(hash()),
+        option::of(ref_name()),
+        option::of(ticket()),
+        option::of(ref_name()),
+        any::<bool>(),
+        0..4usize,
+        0..4usize,
+        option::of(comparison()),
+        option::of(comparison()),
+        option::of(ref_name()),
+        any::<bool>(),
+        option::weighted(0.2, operation()),
+    );
+    let state = (
+        changes(),
+        changes(),
+        submodules(),
+        conflicts(),
+        0..3usize,
+        option::of((ref_name(), text())),
+        option::of(text()),
+        option::of(text()),
+        option::of(text()),
+        option::of(sample::select(vec!['G', 'U', 'X', 'Y', 'N', 'B', 'E'])),
+        option::of(0..1_000_000u64),
+        option::of(0..5usize),
+    );
+
+    (head, state, option::of(forge())).prop_map(
+        |(
+            (commit, branch, ticket, upstream, gone, ahead, behind, push, fallback, tag, wt, op),
+            (
+                working_tree,
+                index,
+                submodules,
+                conflicts,
+                stash,
+                stash_subject,
+                subject,
+                ci,
+                pr,
+                sig,
+                age,
+                stacked,
+            ),
+            forge,
+        )| RepoSnapshot {
+            commit,
+            branch,
+            ticket,
+            upstream_gone: gone && upstream.is_some(),
+            upstream,
+            forge,
+            ahead,
+            behind,
+            push,
+            fallback,
+            tag,
+            worktree: wt,
+            operation: op,
+            working_tree,
+            index,
+            submodules,
+            conflicts,
+            stash,
+            stash_subject,
+            subject,
+            ci,
+            pr,
+            signature: sig,
+            head_age: age,
+            age,
+            fetched: age,
+            commits: stacked,
+            stacked,
+            hidden: stacked,
+            staged_lines: stacked.map(|n| DiffStat {
+                insertions: n,
+                deletions: n,
+            }),
+            ..Default::default()
+        },
+    )
+}
+
+fn options() -> impl Strategy<Value = RenderOptions<'static>> {
+    (
+        any::<bool>(),
+        sample::select(vec![Markup::Ansi, Markup::Tmux, Markup::Html, Markup::Zsh]),
+        sample::select(Theme::ALL.to_vec()),
+        sample::select(vec![Background::Dark, Background::Light]),
+        any::<bool>(),
+        any::<bool>(),
+        any::<bool>(),
+        option::of(0..24usize),
+        option::of(0..45usize),
+        any::<bool>(),
+        any::<bool>(),
+        (
+            option::of(sample::select(vec![HintStyle::Glyph, HintStyle::Text])),
+            sample::select(vec![Truncation::End, Truncation::Middle]),
+            any::<bool>(),
+        ),
+    )
+        .prop_map(
+            |(
+                color,
+                markup,
+                theme,
+                background,
+                ascii,
+                sparse,
+                totals,
+                branch_len,
+                hash_len,
+                detailed_conflicts,
+                full_upstream,
+                (hints, truncation, compact),
+            )| {
+                RenderOptions {
+                    color,
+                    markup,
+                    theme,
+                    background,
+                    ascii,
+                    overrides: None,
+                    sparse,
+                    totals,
+                    branch_len,
+                    truncation,
+                    hash_len,
+                    detailed_conflicts,
+                    full_upstream,
+                    compact,
+                    hints,
+                    hidden: Segments::default(),
+                }
+            },
+        )
+}
+
+const SEGMENTS: [&str; 18] = [
+    "branch",
+    "remote",
+    "div",
+    "push",
+    "push_div",
+    "fallback",
+    "fallback_div",
+    "age",
+    "commits",
+    "stacked",
+    "tag",
+    "worktree",
+    "stash",
+    "conflicts",
+    "wt",
+    "idx",
+    "sub",
+    "subject",
+];
+
+fn template() -> impl Strategy<Value = Template> {
+    proptest::collection::vec(
+        (sample::select(SEGMENTS.to_vec()), "[ :\\[\\]()]{0,2}"),
+        0..8,
+    )
+    .prop_map(|parts| {
+        let template: String = parts
+            .into_iter()
+            .map(|(segment, sep)| format!("{{{segment}:{sep}{{}}}}{sep}"))
+            .collect();
+        Template::parse(&template).expect("generated templates are valid")
+    })
+}
+
+// every escape is a CSI or OSC sequence and neither a style nor a link is left open at the end
+fn assert_balanced(out: &str) -> Result<(), TestCaseError> {
+    let (mut styled, mut linked) = (false, false);
+    let mut rest = out;
+
+    while let Some(start) = rest.find('\x1b') {
+        rest = &rest[start + 1..];
+        if let Some(csi) = rest.strip_prefix('[') {
+            let end = csi
+                .find('m')
+                .ok_or_else(|| TestCaseError::fail("unterminated CSI"))?;
+            styled = !matches!(&csi[..end], "" | "0");
+            rest = &csi[end + 1..];
+        } else if let Some(osc) = rest.strip_prefix("]8;;") {
+            let end = osc
+                .find("\x1b\\")
+                .ok_or_else(|| TestCaseError::fail("unterminated OSC 8"))?;
+            linked = end != 0;
+            rest = &osc[end + 2..];
+        } else {
+            return Err(TestCaseError::fail(format!("stray escape in {out:?}")));
+        }
+    }
+
+    prop_assert!(!styled, "style left open in {out:?}");
+    prop_assert!(!linked, "link left open in {out:?}");
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn escapes_are_balanced(snapshot in snapshot(), options in options()) {
+        assert_balanced(&snapshot.render(&options))?;
+    }
+
+    #[test]
+    fn templates_are_balanced(
+        snapshot in snapshot(),
+        options in options(),
+        template in template(),
+    ) {
+        assert_balanced(&snapshot.render_with(&options, Some(&template)))?;
+    }
+
+    // each side ends up in a prompt of its own
+    #[test]
+    fn split_is_balanced(snapshot in snapshot(), options in options()) {
+        let (left, right) = snapshot.render_split(&options);
+        assert_balanced(&left)?;
+        assert_balanced(&right)?;
+    }
+
+    #[test]
+    fn plain_has_no_control_chars(
+        snapshot in snapshot(),
+        options in options(),
+        template in option::of(template()),
+    ) {
+        let options = RenderOptions { color: false, ..options };
+        let out = snapshot.render_with(&options, template.as_ref());
+        prop_assert!(!out.chars().any(char::is_control), "{out:?}");
+    }
+
+    #[test]
+    fn ascii_is_ascii(
+        snapshot in snapshot(),
+        options in options(),
+        template in option::of(template()),
+    ) {
+        let options = RenderOptions { ascii: true, ..options };
+        let out = snapshot.render_with(&options, template.as_ref());
+        prop_assert!(out.is_ascii(), "{out:?}");
+    }
+
+    #[test]
+    fn html_is_escaped(snapshot in snapshot(), options in options()) {
+        let options = RenderOptions { markup: Markup::Html, ..options };
+        let out = snapshot.render(&options);
+        // only the spans of roles are markup, anything else is text
+        let text = out
+            .replace("</span>", "")
+            .split("<span class=\"epg-")
+            .map(|part| part.split_once("\">").map_or(part, |(_, rest)| rest).to_owned())
+            .collect::<String>();
+        prop_assert!(!text.contains(['<', '>', '"']), "{out:?}");
+    }
+
+    #[test]
+    fn zsh_is_escaped(snapshot in snapshot(), options in options()) {
+        let options = RenderOptions { markup: Markup::Zsh, ..options };
+        let out = snapshot.render(&options);
+        // every `%` starts a style escape or is itself escaped
+        let mut rest = out.as_str();
+        while let Some(at) = rest.find('%') {
+            rest = &rest[at + 1..];
+            prop_assert!(["%", "B", "b", "F{", "f"].iter().any(|s| rest.starts_with(s)), "{out:?}");
+            rest = &rest[1..];
+        }
+    }
+
+    #[test]
+    fn subject_is_capped(snapshot in snapshot(), options in options()) {
+        let options = RenderOptions {
+            color: false,
+            markup: Markup::Ansi,
+            ascii: false,
+            ..options
+        };
+        let template = Template::parse("{subject}").unwrap();
+        let out = snapshot.prompt().render_template(&template, &options);
+        // quoted, the `…` marking a cut is within the length
+        prop_assert!(out.chars().count() <= SUBJECT_LEN + 2, "{out:?}");
+    }
+
+    #[test]
+    fn hash_is_capped(hash in hash(), len in 0..45usize) {
+        let snapshot = RepoSnapshot { commit: Some(hash), ..Default::default() };
+        let options = RenderOptions { hash_len: Some(len), ..Default::default() };
+        let template = Template::parse("{branch}").unwrap();
+        let out = snapshot.prompt().render_template(&template, &options);
+        prop_assert!(out.chars().count() <= len, "{out:?}");
+    }
+
+    #[test]
+    fn branch_is_capped(
+        branch in ref_name(),
+        len in 1..20usize,
+        middle in any::<bool>(),
+        ascii in any::<bool>(),
+    ) {
+        let snapshot = RepoSnapshot {
+            commit: Some("0".repeat(40)),
+            branch: Some(branch),
+            ..Default::default()
+        };
+        let truncation = if middle { Truncation::Middle } else { Truncation::End };
+        let options = RenderOptions {
+            branch_len: Some(len),
+            truncation,
+            ascii,
+            ..Default::default()
+        };
+        let template = Template::parse("{branch}").unwrap();
+        let out = snapshot.prompt().render_template(&template, &options);
+        prop_assert!(out.chars().count() <= len, "{out:?}");
+    }
+
+    // the subject of the latest stash goes with the stash
+    #[test]
+    fn hidden_stash_is_gone(snapshot in snapshot(), options in options()) {
+        let mut hidden = options.hidden;
+        hidden.insert(Segment::Stash);
+        let options = RenderOptions { hidden, ..options };
+        let stashless = RepoSnapshot { stash: 0, stash_subject: None, ..snapshot.clone() };
+        prop_assert_eq!(snapshot.render(&options), stashless.render(&options));
+    }
+
+    // wrappers keep what they rendered until the hash changes
+    #[test]
+    fn hash_follows_state(
+        snapshot in snapshot(),
+        fallback in comparison(),
+        forge in forge(),
+        abbrev in 4..12usize,
+    ) {
+        let before = output::hash(&snapshot);
+        let changed = |change: &dyn Fn(&mut RepoSnapshot)| {
+            let mut changed = snapshot.clone();
+            change(&mut changed);
+            changed
+        };
+
+        let behind = changed(&|s| {
+            let mut fallback = s.fallback.clone().unwrap_or_else(|| fallback.clone());
+            fallback.behind += 1;
+            s.fallback = Some(fallback);
+        });
+        prop_assert_ne!(&before, &output::hash(&behind));
+
+        let forge = changed(&|s| {
+            s.forge = match &s.forge {
+                Some(_) => None,
+                None => Some(forge.clone()),
+            }
+        });
+        prop_assert_ne!(&before, &output::hash(&forge));
+
+        let abbrev = changed(&|s| s.abbrev = Some(s.abbrev.map_or(abbrev, |len| len + 1)));
+        prop_assert_ne!(&before, &output::hash(&abbrev));
+    }
+}
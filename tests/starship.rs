@@ -0,0 +1,27 @@
+//! `--output=starship` turns SGR escapes into style tokens, every parameter of an extended color
+//! belongs to that color.
+
+use epb_prompt_git::output::for_starship;
+
+#[test]
+fn truecolor_components_are_not_sgrs() {
+    // a `0` component would reset, a `1` turn on bold
+    assert_eq!(for_starship("\x1b[38;2;1;0;255mx\x1b[m"), "[x](#0100ff)");
+    assert_eq!(
+        for_starship("\x1b[1;38;2;0;1;0mx\x1b[22my"),
+        "[x](bold #000100)[y](#000100)"
+    );
+    assert_eq!(for_starship("\x1b[48;2;1;1;1;31mx"), "[x](red)");
+}
+
+#[test]
+fn indexed_colors() {
+    assert_eq!(for_starship("\x1b[38;5;1mx"), "[x](red)");
+    assert_eq!(for_starship("\x1b[38;5;136mx\x1b[39my"), "[x](136)y");
+}
+
+#[test]
+fn truncated_colors() {
+    assert_eq!(for_starship("\x1b[38;2;1mx"), "x");
+    assert_eq!(for_starship("\x1b[38;5mx"), "x");
+}
@@ -0,0 +1,85 @@
+//! `format` and `order` layouts, every way a template can be rejected and how it is filled in.
+
+use epb_prompt_git::repo::{RenderOptions, RepoSnapshot, Template};
+
+fn snapshot(stash: usize) -> RepoSnapshot {
+    RepoSnapshot {
+        commit: Some("0123456789abcdef0123456789abcdef01234567".to_owned()),
+        branch: Some("main".to_owned()),
+        stash,
+        ..Default::default()
+    }
+}
+
+fn render(template: &Template, stash: usize) -> String {
+    snapshot(stash)
+        .prompt()
+        .render_template(template, &RenderOptions::default())
+}
+
+fn parse_err(template: &str) -> String {
+    Template::parse(template).expect_err(template)
+}
+
+#[test]
+fn unmatched_close() {
+    assert_eq!(
+        parse_err("{branch}}"),
+        "unmatched `}`, use `}}` for a literal one"
+    );
+    assert_eq!(
+        parse_err("a } b"),
+        "unmatched `}`, use `}}` for a literal one"
+    );
+}
+
+#[test]
+fn unclosed_open() {
+    assert_eq!(parse_err("{branch"), "unclosed `{branch`");
+    assert_eq!(parse_err("{branch} {"), "unclosed `{`");
+    assert_eq!(parse_err("{stash: s[{}]"), "unclosed `{stash`");
+}
+
+#[test]
+fn unknown_segment() {
+    assert_eq!(parse_err("{branch}{nope}"), "unknown segment `nope`");
+    assert_eq!(parse_err("{}"), "unknown segment ``");
+    assert_eq!(
+        Template::from_order(["branch", "nope"]).expect_err("nope"),
+        "unknown segment `nope`"
+    );
+}
+
+#[test]
+fn wrap_needs_one_placeholder() {
+    assert_eq!(parse_err("{stash: s[]}"), "`stash` needs exactly one `{}`");
+    assert_eq!(parse_err("{stash:{}{}}"), "`stash` needs exactly one `{}`");
+}
+
+#[test]
+fn escapes() {
+    let template = Template::parse("{{{branch}}} {{}}").unwrap();
+    assert_eq!(render(&template, 0), "{main} {}");
+}
+
+#[test]
+fn empty_segments_are_elided() {
+    let template = Template::parse("{branch}{stash: s[{}]}!").unwrap();
+    assert_eq!(render(&template, 0), "main!");
+    assert_eq!(render(&template, 2), "main s[2]!");
+}
+
+#[test]
+fn order_decorates_like_the_builtin_layout() {
+    let template = Template::from_order(["branch", "stash"]).unwrap();
+    assert_eq!(render(&template, 0), "main");
+    assert_eq!(render(&template, 2), "main s[2]");
+
+    // nothing to separate the first segment from
+    let template = Template::from_order(["stash", "branch"]).unwrap();
+    assert_eq!(render(&template, 2), "s[2] main");
+    assert_eq!(
+        Template::from_order([]).unwrap(),
+        Template::parse("").unwrap()
+    );
+}